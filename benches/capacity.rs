@@ -0,0 +1,79 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::time::{Duration, Instant};
+
+use nexusq2::{ChannelBuilder, Receiver, Sender};
+
+/// Fill the channel and then drain it so that every send and receive maps an id to a cell
+fn fill_and_drain(sender: &Sender<usize>, receiver: &mut Receiver<usize>, num: usize) {
+    for i in 0..num {
+        sender.send(i).expect("couldn't send");
+    }
+    for _ in 0..num {
+        black_box(receiver.recv().expect("couldn't receive"));
+    }
+}
+
+/// Send and receive across two threads so that the cost of mapping ids is measured alongside the
+/// cost of waiting
+fn threaded(sender: Sender<usize>, mut receiver: Receiver<usize>, num: usize) -> Duration {
+    let start = Instant::now();
+    let handle = std::thread::spawn(move || {
+        for i in 0..num {
+            sender.send(i).expect("couldn't send");
+        }
+    });
+    for _ in 0..num {
+        black_box(receiver.recv().expect("couldn't receive"));
+    }
+    let duration = start.elapsed();
+    handle.join().expect("couldn't join sender");
+    duration
+}
+
+fn capacity(c: &mut Criterion) {
+    let num_elements = 5000;
+    // power of two capacities map ids with a mask while exact capacities use fastmod
+    let configs = [("power_of_two", 1024, false), ("exact", 1000, true)];
+
+    let mut group = c.benchmark_group("capacity_single_thread");
+    group.throughput(Throughput::Elements(num_elements as u64));
+    for (name, size, exact) in configs {
+        group.bench_with_input(BenchmarkId::new(name, size), &size, |b, &size| {
+            let (sender, mut receiver) = ChannelBuilder::new(size)
+                .exact_capacity(exact)
+                .build()
+                .expect("couldn't construct channel");
+            b.iter(|| {
+                let mut remaining = num_elements;
+                while remaining > 0 {
+                    let num = remaining.min(size - 1);
+                    fill_and_drain(&sender, &mut receiver, num);
+                    remaining -= num;
+                }
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("capacity_threaded");
+    group.throughput(Throughput::Elements(num_elements as u64));
+    for (name, size, exact) in configs {
+        group.bench_with_input(BenchmarkId::new(name, size), &size, |b, &size| {
+            b.iter_custom(|iters| {
+                (0..iters)
+                    .map(|_| {
+                        let (sender, receiver) = ChannelBuilder::new(size)
+                            .exact_capacity(exact)
+                            .build()
+                            .expect("couldn't construct channel");
+                        threaded(sender, receiver, num_elements)
+                    })
+                    .sum()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, capacity);
+criterion_main!(benches);
@@ -14,7 +14,7 @@ where
 {
     #[inline(always)]
     fn test_recv(&mut self) -> T {
-        self.recv()
+        self.recv().expect("couldn't receive")
     }
 
     fn another(&self) -> Self {
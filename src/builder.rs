@@ -0,0 +1,342 @@
+use crate::wait_strategy::{hybrid::HybridWait, Take, Wait};
+use crate::{
+    DeliveryMode, Metrics, NexusError, NexusQ, NoReceiverPolicy, OverflowPolicy, Receiver, Sender,
+    StallPolicy,
+};
+use alloc::sync::Arc;
+use core::fmt::{Debug, Formatter};
+use portable_atomic::AtomicUsize;
+
+/// Configures and constructs a nexusq channel.
+///
+/// Every option other than the capacity has a default so only the options that matter need to be
+/// set. The options are validated when the channel is built.
+///
+/// # Examples
+/// ```rust
+/// use nexusq2::wait_strategy::hybrid::HybridWait;
+/// use nexusq2::{ChannelBuilder, DeliveryMode};
+/// let (sender, mut receiver) = ChannelBuilder::new(4)
+///     .delivery_mode(DeliveryMode::WorkQueue)
+///     .writer_wait_strategy(HybridWait::default())
+///     .reader_wait_strategy(HybridWait::default)
+///     .name("jobs")
+///     .build()
+///     .expect("couldn't construct channel");
+/// sender.send(42).expect("couldn't send");
+/// assert_eq!(receiver.recv(), Ok(42));
+/// assert_eq!(receiver.name(), Some("jobs"));
+/// ```
+pub struct ChannelBuilder<W = HybridWait, F = fn() -> HybridWait> {
+    capacity: usize,
+    exact_capacity: bool,
+    writer_ws: W,
+    reader_ws: F,
+    mode: DeliveryMode,
+    no_receiver_policy: NoReceiverPolicy,
+    overflow_policy: OverflowPolicy,
+    watermarks: Option<(usize, usize)>,
+    stall_policy: StallPolicy,
+    name: Option<Arc<str>>,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl<W, F> Debug for ChannelBuilder<W, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        // the wait strategies and metrics hooks aren't Debug
+        f.debug_struct("ChannelBuilder")
+            .field("capacity", &self.capacity)
+            .field("exact_capacity", &self.exact_capacity)
+            .field("mode", &self.mode)
+            .field("no_receiver_policy", &self.no_receiver_policy)
+            .field("overflow_policy", &self.overflow_policy)
+            .field("watermarks", &self.watermarks)
+            .field("stall_policy", &self.stall_policy)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChannelBuilder {
+    /// Start building a channel with a buffer of the given size. The size must be at least 2, and no
+    /// larger than [`isize::MAX`].
+    ///
+    /// The channel defaults to [`HybridWait`] wait strategies for both the senders and receivers,
+    /// [`DeliveryMode::Broadcast`], [`NoReceiverPolicy::Disconnect`], [`OverflowPolicy::Block`] and
+    /// [`StallPolicy::Never`].
+    /// The capacity is rounded up
+    /// to the next power of two unless [`ChannelBuilder::exact_capacity`] is set.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            exact_capacity: false,
+            writer_ws: HybridWait::default(),
+            reader_ws: HybridWait::default,
+            mode: DeliveryMode::default(),
+            no_receiver_policy: NoReceiverPolicy::default(),
+            overflow_policy: OverflowPolicy::default(),
+            watermarks: None,
+            stall_policy: StallPolicy::default(),
+            name: None,
+            metrics: None,
+        }
+    }
+}
+
+impl<W, F> ChannelBuilder<W, F> {
+    /// Set the size of the channel buffer. This must be at least 2, and no larger than [`isize::MAX`]
+    #[must_use]
+    pub const fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Use the capacity as given rather than rounding it up to the next power of two. Rounding up
+    /// lets ids be mapped to cells with a mask which is a little faster than the multiplications
+    /// needed for other sizes, but a large channel can end up with almost twice the memory it
+    /// asked for.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use nexusq2::ChannelBuilder;
+    /// let (sender, receiver) = ChannelBuilder::new(1000)
+    ///     .build::<usize>()
+    ///     .expect("couldn't construct channel");
+    /// assert_eq!(sender.capacity(), 1024);
+    /// let (sender, receiver) = ChannelBuilder::new(1000)
+    ///     .exact_capacity(true)
+    ///     .build::<usize>()
+    ///     .expect("couldn't construct channel");
+    /// assert_eq!(sender.capacity(), 1000);
+    /// ```
+    #[must_use]
+    pub const fn exact_capacity(mut self, exact_capacity: bool) -> Self {
+        self.exact_capacity = exact_capacity;
+        self
+    }
+
+    /// Set the wait strategy the senders use to wait on each other
+    #[must_use]
+    pub fn writer_wait_strategy<W2>(self, writer_ws: W2) -> ChannelBuilder<W2, F>
+    where
+        W2: Take<AtomicUsize> + 'static,
+    {
+        ChannelBuilder {
+            capacity: self.capacity,
+            exact_capacity: self.exact_capacity,
+            writer_ws,
+            reader_ws: self.reader_ws,
+            mode: self.mode,
+            no_receiver_policy: self.no_receiver_policy,
+            overflow_policy: self.overflow_policy,
+            watermarks: self.watermarks,
+            stall_policy: self.stall_policy,
+            name: self.name,
+            metrics: self.metrics,
+        }
+    }
+
+    /// Set the function that produces the wait strategies used to wait on the receivers. One wait
+    /// strategy is produced for every cell in the buffer.
+    #[must_use]
+    pub fn reader_wait_strategy<F2, R>(self, reader_ws: F2) -> ChannelBuilder<W, F2>
+    where
+        F2: Fn() -> R,
+        R: Wait<AtomicUsize> + 'static + Clone,
+    {
+        ChannelBuilder {
+            capacity: self.capacity,
+            exact_capacity: self.exact_capacity,
+            writer_ws: self.writer_ws,
+            reader_ws,
+            mode: self.mode,
+            no_receiver_policy: self.no_receiver_policy,
+            overflow_policy: self.overflow_policy,
+            watermarks: self.watermarks,
+            stall_policy: self.stall_policy,
+            name: self.name,
+            metrics: self.metrics,
+        }
+    }
+
+    /// Set how values are delivered to the receivers
+    #[must_use]
+    pub const fn delivery_mode(mut self, mode: DeliveryMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set what sends do while there are no receivers
+    #[must_use]
+    pub const fn no_receiver_policy(mut self, policy: NoReceiverPolicy) -> Self {
+        self.no_receiver_policy = policy;
+        self
+    }
+
+    /// Set what sends do when the channel is full. The policy applies to every kind of send,
+    /// including reservations and sends through the [`Sink`](futures_util::Sink) implementation.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use nexusq2::{ChannelBuilder, DeliveryMode, OverflowPolicy, SendError};
+    /// let (sender, mut receiver) = ChannelBuilder::new(2)
+    ///     .delivery_mode(DeliveryMode::WorkQueue)
+    ///     .overflow_policy(OverflowPolicy::DropOldest)
+    ///     .build()
+    ///     .expect("couldn't construct channel");
+    /// sender.send(1).expect("couldn't send");
+    /// sender.send(2).expect("couldn't send");
+    /// sender.send(3).expect("couldn't send");
+    /// assert_eq!(sender.dropped_count(), 1);
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// assert_eq!(receiver.recv(), Ok(3));
+    /// ```
+    #[must_use]
+    pub const fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Track the channel's occupancy against a low and a high watermark. The channel moves to
+    /// [`Watermark::High`](crate::Watermark::High) once the occupancy reaches `high` and back to
+    /// [`Watermark::Low`](crate::Watermark::Low) once it falls to `low`. Producers can watch the
+    /// watermark with [`Sender::watermark`] and wait for it with [`Sender::wait_for_watermark`] to
+    /// slow down before the channel is full.
+    ///
+    /// The occupancy is checked after every send and receive which takes the locks used by
+    /// [`Sender::len`], so only set watermarks on channels that need them. Broadcast channels hold one value fewer than their
+    /// capacity so a high watermark equal to the capacity is never reached.
+    #[must_use]
+    pub const fn watermarks(mut self, low: usize, high: usize) -> Self {
+        self.watermarks = Some((low, high));
+        self
+    }
+
+    /// Set when broadcast receivers that hold up the channel are evicted. A slow receiver normally
+    /// makes senders wait once the channel is full. An evicted receiver gives up its place in the
+    /// channel so that the senders can carry on, and every operation on it fails with
+    /// [`RecvError::Evicted`](crate::RecvError::Evicted).
+    ///
+    /// # Examples
+    /// ```rust
+    /// use nexusq2::{ChannelBuilder, RecvError, StallPolicy};
+    /// use std::time::Duration;
+    /// let (sender, mut receiver) = ChannelBuilder::new(2)
+    ///     .stall_policy(StallPolicy::Timeout(Duration::from_millis(10)))
+    ///     .build()
+    ///     .expect("couldn't construct channel");
+    /// let mut stalled = receiver.clone();
+    /// sender.send(1).expect("couldn't send");
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// // the stalled receiver hasn't read the first value so the second send evicts it
+    /// sender.send(2).expect("couldn't send");
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// assert_eq!(stalled.recv(), Err(RecvError::Evicted));
+    /// ```
+    #[must_use]
+    pub const fn stall_policy(mut self, policy: StallPolicy) -> Self {
+        self.stall_policy = policy;
+        self
+    }
+
+    /// Name the channel. The name is available from [`Sender::name`] and [`Receiver::name`] and
+    /// makes channels easier to tell apart when debugging.
+    #[must_use]
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set hooks that are called as values move through the channel
+    #[must_use]
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+impl<W, F, R> ChannelBuilder<W, F>
+where
+    W: Take<AtomicUsize> + 'static,
+    F: Fn() -> R,
+    R: Wait<AtomicUsize> + 'static + Clone,
+{
+    /// Build the channel and return its first sender and receiver
+    ///
+    /// # Errors
+    /// - [`NexusError::BufferTooSmall`] if the buffer size is less than 2
+    /// - [`NexusError::BufferTooLarge`] if the buffer size is larger than [`isize::MAX`]
+    /// - [`NexusError::InvalidWatermarks`] if the low watermark isn't below the high watermark or
+    ///   the high watermark is above the capacity
+    /// - [`NexusError::StallPolicyRequiresBroadcast`] if a [`StallPolicy`] is used without
+    ///   [`DeliveryMode::Broadcast`]
+    /// - [`NexusError::InvalidMaxLag`] if [`StallPolicy::MaxLag`] is zero or at least the capacity
+    ///   minus one
+    pub fn build<T>(self) -> Result<(Sender<T>, Receiver<T>), NexusError> {
+        let nexus = Arc::new(self.build_nexus()?);
+        let receiver = Receiver::new(nexus.clone());
+        let sender = Sender::new(nexus);
+        Ok((sender, receiver))
+    }
+
+    /// Build a channel that has no receivers yet. Receivers can be created later using
+    /// [`Sender::subscribe`]. See [`make_sender`](crate::make_sender).
+    ///
+    /// # Errors
+    /// - [`NexusError::BufferTooSmall`] if the buffer size is less than 2
+    /// - [`NexusError::BufferTooLarge`] if the buffer size is larger than [`isize::MAX`]
+    /// - [`NexusError::InvalidWatermarks`] if the low watermark isn't below the high watermark or
+    ///   the high watermark is above the capacity
+    /// - [`NexusError::StallPolicyRequiresBroadcast`] if a [`StallPolicy`] is used without
+    ///   [`DeliveryMode::Broadcast`]
+    /// - [`NexusError::InvalidMaxLag`] if [`StallPolicy::MaxLag`] is zero or at least the capacity
+    ///   minus one
+    ///
+    /// # Examples
+    /// ```rust
+    /// use nexusq2::{ChannelBuilder, NoReceiverPolicy};
+    /// let sender = ChannelBuilder::new(4)
+    ///     .no_receiver_policy(NoReceiverPolicy::Discard)
+    ///     .build_sender()
+    ///     .expect("couldn't construct channel");
+    /// sender.send(1).expect("couldn't send");
+    /// let mut receiver = sender.subscribe();
+    /// sender.send(2).expect("couldn't send");
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// ```
+    pub fn build_sender<T>(self) -> Result<Sender<T>, NexusError> {
+        Ok(Sender::new(Arc::new(self.build_nexus()?)))
+    }
+
+    fn build_nexus<T>(self) -> Result<NexusQ<T>, NexusError> {
+        if self
+            .watermarks
+            .is_some_and(|(low, high)| low >= high || high > self.capacity)
+        {
+            return Err(NexusError::InvalidWatermarks);
+        }
+        if self.stall_policy != StallPolicy::Never && self.mode != DeliveryMode::Broadcast {
+            return Err(NexusError::StallPolicyRequiresBroadcast);
+        }
+        if let StallPolicy::MaxLag(max_lag) = self.stall_policy {
+            if max_lag == 0 || max_lag.saturating_add(2) > self.capacity {
+                return Err(NexusError::InvalidMaxLag);
+            }
+        }
+        let mut nexus = NexusQ::with_strategies(
+            self.capacity,
+            self.writer_ws,
+            self.reader_ws,
+            self.mode,
+            self.exact_capacity,
+        )?;
+        nexus.no_receiver_policy = self.no_receiver_policy;
+        nexus.overflow_policy = self.overflow_policy;
+        nexus.watermarks = self.watermarks;
+        nexus.stall_policy = self.stall_policy;
+        nexus.name = self.name;
+        nexus.metrics = self.metrics;
+        Ok(nexus)
+    }
+}
@@ -1,15 +1,57 @@
-use crate::wait_strategy::{hybrid::HybridWait, AsyncEventGuard, Wait, WaitError};
+use crate::wait_strategy::{
+    hybrid::HybridWait, AsyncEventGuard, Sequence, Wait, WaitError, Waitable,
+};
 use core::fmt::{Debug, Formatter};
 use portable_atomic::{AtomicUsize, Ordering};
 use std::cell::UnsafeCell;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::ptr;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::Thread;
 use std::time::{Duration, Instant};
 
+/// Set on the read counter while a lossy writer is replacing the value in the cell
+const WRITING: usize = 1 << (usize::BITS - 1);
+
+/// Wakes a thread parked in a blocking wait
+struct Unparker(Thread);
+
+impl Wake for Unparker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Park the thread until `poll` is ready or the deadline has passed. `poll` registers the thread
+/// to be woken the same way an async task would be.
+fn block_on(
+    deadline: Option<Instant>,
+    mut poll: impl FnMut(&mut Context<'_>) -> Poll<()>,
+) -> Result<(), WaitError> {
+    let waker = Waker::from(Arc::new(Unparker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    while poll(&mut cx).is_pending() {
+        match deadline {
+            None => std::thread::park(),
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(WaitError::Timeout);
+                }
+                std::thread::park_timeout(deadline - now);
+            }
+        }
+    }
+    Ok(())
+}
+
 pub struct Cell<T> {
     value: UnsafeCell<Option<T>>,
     read_counter: AtomicUsize,
-    current_id: AtomicUsize,
+    // The number of work queue consumers or consumer groups that have yet to claim the value
+    consumers: AtomicUsize,
+    current_id: Sequence,
     wait_strategy: Box<dyn Wait<AtomicUsize>>,
 }
 
@@ -22,6 +64,7 @@ where
         f.debug_struct("Cell")
             .field("value", &self.value)
             .field("read_counter", &self.read_counter)
+            .field("consumers", &self.consumers)
             .field("current_id", &self.current_id)
             .finish()
     }
@@ -40,26 +83,51 @@ impl<T> Cell<T> {
         Self {
             value: UnsafeCell::new(None),
             read_counter: AtomicUsize::new(0),
-            current_id: AtomicUsize::new(usize::MAX),
+            consumers: AtomicUsize::new(0),
+            current_id: Sequence::new(0),
             wait_strategy: Box::new(ws),
         }
     }
 
-    pub fn wait_for_write_safe(&self) -> bool {
-        if self.read_counter.load(Ordering::Acquire) == 0 {
+    /// Wait for the cell to become safe to write to. The wait also ends once `interrupted` returns
+    /// true, which is checked whenever the writer is woken. Returns true if the cell was safe to
+    /// write to straight away.
+    pub fn wait_for_write_safe(&self, interrupted: impl Fn() -> bool) -> bool {
+        if self.safe_to_write() {
             return true;
         }
-        self.wait_strategy.wait_for(&self.read_counter, &0);
+        let _ = self.block_on_write_safe(None, interrupted);
         false
     }
 
-    pub fn wait_for_write_safe_before(&self, deadline: Instant) -> Result<bool, WaitError> {
-        if self.read_counter.load(Ordering::Acquire) == 0 {
+    /// Like [`Cell::wait_for_write_safe`] but gives up once the deadline has passed
+    pub fn wait_for_write_safe_before(
+        &self,
+        deadline: Instant,
+        interrupted: impl Fn() -> bool,
+    ) -> Result<bool, WaitError> {
+        if self.safe_to_write() {
             return Ok(true);
         }
-        self.wait_strategy
-            .wait_until(&self.read_counter, &0, deadline)?;
-        Ok(false)
+        self.block_on_write_safe(Some(deadline), interrupted)
+            .map(|()| false)
+    }
+
+    /// Park the thread until [`Cell::poll_write_safe`] is ready. The wait strategy only wakes the
+    /// writer, so `interrupted` can end the wait on conditions the strategy doesn't check.
+    fn block_on_write_safe(
+        &self,
+        deadline: Option<Instant>,
+        interrupted: impl Fn() -> bool,
+    ) -> Result<(), WaitError> {
+        let mut event_listener = None;
+        block_on(deadline, |cx| {
+            if self.poll_write_safe(cx, &mut event_listener).is_ready() || interrupted() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
     }
 
     pub fn poll_write_safe(
@@ -67,13 +135,42 @@ impl<T> Cell<T> {
         cx: &mut Context<'_>,
         event_listener: &mut Option<Pin<Box<dyn AsyncEventGuard>>>,
     ) -> Poll<()> {
-        self.wait_strategy
-            .poll(cx, &self.read_counter, &0, event_listener)
+        // Neither counter can increase once the write head has been claimed for this cell
+        if Wait::<AtomicUsize>::poll(
+            self.wait_strategy.as_ref(),
+            cx,
+            &self.read_counter,
+            &0,
+            event_listener,
+        )
+        .is_pending()
+        {
+            return Poll::Pending;
+        }
+        Wait::<AtomicUsize>::poll(
+            self.wait_strategy.as_ref(),
+            cx,
+            &self.consumers,
+            &0,
+            event_listener,
+        )
     }
 
+    /// Wait for the cell to publish the expected id. The cell must not be able to publish a later id
+    /// before the wait returns, which holds for broadcast receivers as they hold the cell before it.
     pub fn wait_for_published(&self, expected_published_id: usize) {
-        self.wait_strategy
-            .wait_for(&self.current_id, &expected_published_id);
+        if !self.current_id.check(&expected_published_id) {
+            self.wait_strategy
+                .wait_for(self.current_id.as_atomic(), &expected_published_id);
+        }
+    }
+
+    /// Wait for the cell to publish the expected id or any id after it. Used where other receivers
+    /// or lossy senders can move the cell past the expected id while waiting.
+    pub fn wait_for_published_or_later(&self, expected_published_id: usize) {
+        if !self.current_id.check(&expected_published_id) {
+            let _ = self.block_on_published(expected_published_id, None);
+        }
     }
 
     pub fn poll_published(
@@ -82,27 +179,61 @@ impl<T> Cell<T> {
         expected_published_id: usize,
         event_listener: &mut Option<Pin<Box<dyn AsyncEventGuard>>>,
     ) -> Poll<()> {
-        self.wait_strategy
-            .poll(cx, &self.current_id, &expected_published_id, event_listener)
+        loop {
+            if self.current_id.check(&expected_published_id) {
+                *event_listener = None;
+                return Poll::Ready(());
+            }
+            if self
+                .wait_strategy
+                .poll(
+                    cx,
+                    self.current_id.as_atomic(),
+                    &expected_published_id,
+                    event_listener,
+                )
+                .is_pending()
+            {
+                // Every publish notifies the listener, so if a later id was published before it
+                // was registered it has to be caught here
+                if self.current_id.check(&expected_published_id) {
+                    *event_listener = None;
+                    return Poll::Ready(());
+                }
+                return Poll::Pending;
+            }
+        }
     }
     pub fn wait_for_published_until(
         &self,
         expected_published_id: usize,
         deadline: Instant,
     ) -> Result<(), WaitError> {
-        self.wait_strategy
-            .wait_until(&self.current_id, &expected_published_id, deadline)
+        if self.current_id.check(&expected_published_id) {
+            return Ok(());
+        }
+        self.block_on_published(expected_published_id, Some(deadline))
     }
+
+    /// Park the thread until [`Cell::poll_published`] is ready. Wait strategies only wait for exact
+    /// values so the thread is woken by every publish and checks for later ids itself.
+    fn block_on_published(
+        &self,
+        expected_published_id: usize,
+        deadline: Option<Instant>,
+    ) -> Result<(), WaitError> {
+        let mut event_listener = None;
+        block_on(deadline, |cx| {
+            self.poll_published(cx, expected_published_id, &mut event_listener)
+        })
+    }
+
     pub fn wait_for_published_with_timeout(
         &self,
         expected_published_id: usize,
         timeout: Duration,
     ) -> Result<(), WaitError> {
-        self.wait_strategy.wait_until(
-            &self.current_id,
-            &expected_published_id,
-            Instant::now() + timeout,
-        )
+        self.wait_for_published_until(expected_published_id, Instant::now() + timeout)
     }
 
     pub fn get_published(&self) -> usize {
@@ -114,14 +245,122 @@ impl<T> Cell<T> {
 impl<T> Cell<T> {
     pub fn safe_to_write(&self) -> bool {
         self.read_counter.load(Ordering::Acquire) == 0
+            && self.consumers.load(Ordering::Acquire) == 0
     }
 
-    pub fn write_and_publish(&self, value: T, id: usize) {
-        let dst = UnsafeCell::raw_get(&self.value);
+    /// Write the value to the cell and publish it. `num_consumers` is the number of work queue
+    /// consumers or consumer groups that must claim the value before the cell is safe to write to again.
+    pub fn write_and_publish(&self, value: T, id: usize, num_consumers: usize) {
+        let dst = UnsafeCell::raw_get(ptr::addr_of!(self.value));
         let old_value = unsafe { (*dst).replace(value) };
+        self.publish_for(id, num_consumers);
+        drop(old_value);
+    }
+
+    /// Fill the value in the cell in place and publish it. The cell holds the value previously
+    /// written to it unless a receiver moved it out. Returns false without publishing anything if
+    /// the cell is left empty.
+    ///
+    /// # Safety
+    /// The caller must hold the write head for `id` and the cell must be safe to write to
+    pub unsafe fn fill_and_publish(
+        &self,
+        fill: impl FnOnce(&mut Option<T>),
+        id: usize,
+        num_consumers: usize,
+    ) -> bool {
+        let value = &mut *UnsafeCell::raw_get(ptr::addr_of!(self.value));
+        fill(value);
+        if value.is_none() {
+            return false;
+        }
+        self.publish_for(id, num_consumers);
+        true
+    }
+
+    /// Fill the value in the cell in place and publish it without waiting for it to be read. Only
+    /// readers that are part way through reading the cell are waited on. Used in lossy mode.
+    ///
+    /// Returns false without publishing anything if the cell is left empty. If `fill` panics the
+    /// cell is unlocked without publishing.
+    ///
+    /// # Safety
+    /// The caller must hold the write head for `id`
+    pub unsafe fn fill_and_overwrite(&self, fill: impl FnOnce(&mut Option<T>), id: usize) -> bool {
+        self.lock_for_overwrite();
+        let unlock = OverwriteLock(self);
+        let value = &mut *UnsafeCell::raw_get(ptr::addr_of!(self.value));
+        fill(value);
+        let filled = value.is_some();
+        if filled {
+            self.current_id.fetch_max(id, Ordering::Release);
+        }
+        drop(unlock);
+        filled
+    }
+
+    /// Overwrite the value in the cell without waiting for it to be read and publish it. Only readers
+    /// that are part way through reading the cell are waited on. Used in lossy mode.
+    ///
+    /// If a later id has already been published to the cell the value is dropped instead as it would
+    /// have been overwritten anyway.
+    pub fn overwrite_and_publish(&self, value: T, id: usize) {
+        self.lock_for_overwrite();
+        let old_value = if self.current_id.load(Ordering::Relaxed) < id {
+            let dst = UnsafeCell::raw_get(ptr::addr_of!(self.value));
+            unsafe { (*dst).replace(value) }
+        } else {
+            Some(value)
+        };
+        self.current_id.fetch_max(id, Ordering::Release);
+        self.unlock_overwrite();
+        drop(old_value);
+    }
+
+    /// Publish the id without writing a new value to the cell
+    pub fn publish(&self, id: usize) {
         self.current_id.store(id, Ordering::Release);
         self.wait_strategy.notify_all();
-        drop(old_value);
+    }
+
+    /// Publish the value written to the cell for `id`
+    fn publish_for(&self, id: usize, num_consumers: usize) {
+        if num_consumers > 0 {
+            // nobody else can touch the counter until the id is published
+            self.consumers.store(num_consumers, Ordering::Relaxed);
+        }
+        self.publish(id);
+    }
+
+    /// Wake everyone waiting on the cell so that they can check the state of the channel again
+    pub fn notify_all(&self) {
+        self.wait_strategy.notify_all();
+    }
+
+    /// Wait for readers part way through reading the cell and stop any more from starting
+    fn lock_for_overwrite(&self) {
+        while self
+            .read_counter
+            .compare_exchange(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            Wait::<AtomicUsize>::wait_for(self.wait_strategy.as_ref(), &self.read_counter, &0);
+        }
+    }
+
+    fn unlock_overwrite(&self) {
+        self.read_counter.fetch_and(!WRITING, Ordering::Release);
+        self.wait_strategy.notify_all();
+    }
+}
+
+/// Unlocks a cell locked for overwriting when dropped so that a panicking writer doesn't leave
+/// readers waiting forever
+struct OverwriteLock<'a, T>(&'a Cell<T>);
+
+impl<T> Drop for OverwriteLock<'_, T> {
+    fn drop(&mut self) {
+        self.0.unlock_overwrite();
     }
 }
 
@@ -139,19 +378,106 @@ impl<T> Cell<T> {
     pub fn move_to(&self) {
         self.read_counter.fetch_add(1, Ordering::Relaxed);
     }
+
+    /// Take the value out of the cell and release the consumer's hold on it.
+    ///
+    /// # Safety
+    /// The caller must have claimed the published value in this cell as a work queue consumer
+    pub unsafe fn take(&self) -> T {
+        let value = self.move_out();
+        self.release();
+        value
+    }
+
+    /// Move the value out of the cell leaving it empty.
+    ///
+    /// # Safety
+    /// The cell must hold a published value that no other receiver will read
+    pub unsafe fn move_out(&self) -> T {
+        (*UnsafeCell::raw_get(ptr::addr_of!(self.value)))
+            .take()
+            .unwrap_unchecked()
+    }
+
+    /// Release a work queue consumer's or consumer group's hold on the cell. This is sequentially
+    /// consistent so that the consumer can then check if the channel has been closed.
+    pub fn release(&self) {
+        let old = self.consumers.fetch_sub(1, Ordering::SeqCst);
+        debug_assert!(old >= 1);
+        if old == 1 {
+            self.wait_strategy.notify_all();
+        }
+    }
+
+    /// Returns true if a broadcast receiver holds the cell
+    pub fn is_held(&self) -> bool {
+        self.read_counter.load(Ordering::Acquire) != 0
+    }
+
+    /// Returns true if every consumer has released the cell
+    pub fn is_released(&self) -> bool {
+        self.consumers.load(Ordering::SeqCst) == 0
+    }
+
+    /// Returns true if the caller's claim is the only one that hasn't been released
+    pub fn is_last_claim(&self) -> bool {
+        self.consumers.load(Ordering::SeqCst) == 1
+    }
+
+    /// Returns true if the cell holds a value. Values that have been moved out leave the cell empty.
+    ///
+    /// # Safety
+    /// The value must not be written to or moved out of the cell while this is called
+    pub unsafe fn has_value(&self) -> bool {
+        (*UnsafeCell::raw_get(ptr::addr_of!(self.value))).is_some()
+    }
+
+    /// Borrow the value in the cell.
+    ///
+    /// # Safety
+    /// The cell must hold a published value and the caller must stop it from being overwritten for
+    /// the lifetime of the borrow
+    pub unsafe fn get(&self) -> &T {
+        (*UnsafeCell::raw_get(ptr::addr_of!(self.value)))
+            .as_ref()
+            .unwrap_unchecked()
+    }
+
+    /// Hold the cell if it still holds the value published with `id`. Used in lossy mode where writers
+    /// don't wait for readers to move on. A held cell must be released using [`Cell::move_from`].
+    ///
+    /// Returns false if the cell is being written to or no longer holds `id`.
+    pub fn try_hold_published(&self, id: usize) -> bool {
+        let old = self.read_counter.fetch_add(1, Ordering::Acquire);
+        if old & WRITING == 0 && self.current_id.load(Ordering::Acquire) == id {
+            return true;
+        }
+        self.move_from();
+        false
+    }
 }
 impl<T> Cell<T>
 where
     T: Clone,
 {
     pub unsafe fn read(&self) -> T {
-        (*UnsafeCell::raw_get(&self.value))
-            .as_ref()
-            .unwrap_unchecked()
-            .clone()
+        self.get().clone()
     }
 
     pub fn read_opt(&self) -> Option<T> {
-        unsafe { (*UnsafeCell::raw_get(&self.value)).clone() }
+        unsafe { (*UnsafeCell::raw_get(ptr::addr_of!(self.value))).clone() }
+    }
+
+    /// Read the value published with `id` without holding the cell afterwards. Used in lossy mode
+    /// where writers don't wait for readers to move on.
+    ///
+    /// Returns None if the cell is being written to or no longer holds `id`.
+    pub fn try_read_published(&self, id: usize) -> Option<T> {
+        if !self.try_hold_published(id) {
+            return None;
+        }
+        let value = unsafe { self.read() };
+        self.move_from();
+        Some(value)
     }
 }
@@ -0,0 +1,72 @@
+use crate::NexusQ;
+use alloc::sync::Arc;
+use core::fmt::{Debug, Formatter};
+
+/// A handle that can close a `NexusQ` channel without being able to send or receive on it.
+///
+/// This handle can be cloned and sent to other threads. It doesn't keep the channel open, dropping
+/// it has no effect on the channel.
+///
+/// Once closed every send will fail with [`SendError::Disconnected`](crate::SendError::Disconnected).
+/// Receivers will read any values that were sent before the channel was closed and then return
+/// [`RecvError::Disconnected`](crate::RecvError::Disconnected).
+pub struct Closer<T> {
+    nexus: Arc<NexusQ<T>>,
+}
+
+impl<T> Debug for Closer<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Closer")
+            .field("nexus", &self.nexus)
+            .finish()
+    }
+}
+
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T> Send for Closer<T> {}
+unsafe impl<T> Sync for Closer<T> {}
+
+impl<T> Clone for Closer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            nexus: self.nexus.clone(),
+        }
+    }
+}
+
+impl<T> Closer<T> {
+    pub(crate) const fn new(nexus: Arc<NexusQ<T>>) -> Self {
+        Self { nexus }
+    }
+
+    /// Close the channel. This will not block.
+    ///
+    /// Receivers waiting for a new value and senders waiting for a free slot are woken. Waiting
+    /// senders return their value in [`SendError::Disconnected`](crate::SendError::Disconnected).
+    /// Closing an already closed channel has no effect.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, RecvError, SendError};
+    /// let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+    /// let closer = sender.closer();
+    /// sender.send(1).expect("couldn't send");
+    /// closer.close();
+    /// assert!(closer.is_closed());
+    /// assert_eq!(sender.send(2), Err(SendError::Disconnected(Some(2))));
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+    /// ```
+    pub fn close(&self) {
+        self.nexus.close();
+    }
+
+    /// Returns true if the channel has been closed
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.nexus.is_closed()
+    }
+}
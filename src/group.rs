@@ -0,0 +1,27 @@
+use alloc::sync::Arc;
+use portable_atomic::{AtomicUsize, Ordering};
+
+/// A named consumer group. Every group receives every value sent to the channel but within a group
+/// each value is received by only one of its members.
+#[derive(Debug)]
+pub struct Group {
+    pub name: Arc<str>,
+    // The next id to be claimed by a member of the group
+    pub read_head: AtomicUsize,
+    pub num_members: AtomicUsize,
+}
+
+impl Group {
+    /// Create a group with a single member that starts receiving from `start_id`
+    pub fn new(name: &str, start_id: usize) -> Self {
+        Self {
+            name: Arc::from(name),
+            read_head: AtomicUsize::new(start_id),
+            num_members: AtomicUsize::new(1),
+        }
+    }
+
+    pub fn add_member(&self) {
+        self.num_members.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -20,6 +20,8 @@
 //! The channel is constructed using the [`make_channel`] function, which takes a buffer size as an
 //! argument. The buffer size must be at least 2, and no larger than [`isize::MAX`].
 //!
+//! Channels that need more than the defaults are configured using [`ChannelBuilder`].
+//!
 //! The channel is then used by sending and receiving values using the [`Sender`] and [`Receiver`]
 //! types respectively.
 //!
@@ -29,8 +31,8 @@
 //! let (sender, mut receiver) = nexusq2::make_channel(4).expect("couldn't construct channel");
 //! sender.send(42).expect("couldn't send");
 //! sender.send(2).expect("couldn't send");
-//! assert_eq!(receiver.recv(), 42);
-//! assert_eq!(receiver.recv(), 2);
+//! assert_eq!(receiver.recv(), Ok(42));
+//! assert_eq!(receiver.recv(), Ok(2));
 //! ```
 
 #![warn(future_incompatible)]
@@ -49,22 +51,41 @@
 extern crate alloc;
 extern crate core;
 
+mod builder;
 mod cell;
+mod closer;
+mod group;
+mod metrics;
+mod position;
 pub(crate) mod prelude;
 mod receiver;
 mod sender;
+mod timer;
 pub mod wait_strategy;
 
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt::{Debug, Formatter};
-use portable_atomic::AtomicUsize;
-use prelude::FastMod;
+use group::Group;
+use portable_atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use position::Position;
+use prelude::{Divisor, FastMod};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::task::Context;
+use std::time::Instant;
 use thiserror::Error as ThisError;
 
-pub use receiver::{Receiver, RecvError};
-pub use sender::{SendError, Sender};
-use wait_strategy::{hybrid::HybridWait, Take, Wait};
+pub use builder::ChannelBuilder;
+pub use closer::Closer;
+pub use metrics::Metrics;
+pub use receiver::{PeekFuture, Received, Receiver, RecvError, RecvFuture, RecvRef, StallPolicy};
+pub use sender::{
+    NoReceiverPolicy, OverflowPolicy, Permit, ReserveFuture, SendError, SendFuture, Sender, Slot,
+    Watermark, WatermarkFuture,
+};
+use timer::Deadline;
+use wait_strategy::{Take, Takeable, Wait};
 
 /// Errors produces by the core of a nexus channel.
 #[derive(Debug, ThisError, Eq, PartialEq, Copy, Clone)]
@@ -75,13 +96,85 @@ pub enum NexusError {
     /// The buffer size cannot be larger than [`isize::MAX`]
     #[error("nexusq channel buffers cannot be larger than isize::MAX")]
     BufferTooLarge,
+    /// Consumer groups can only be used with [`DeliveryMode::Broadcast`] channels
+    #[error("consumer groups can only be used with broadcast channels")]
+    GroupsRequireBroadcast,
+    /// The low watermark must be below the high watermark which can't be above the capacity
+    #[error("nexusq channel watermarks must satisfy low < high <= capacity")]
+    InvalidWatermarks,
+    /// Only receivers of [`DeliveryMode::Broadcast`] channels can be evicted by a [`StallPolicy`]
+    #[error("stall policies can only be used with broadcast channels")]
+    StallPolicyRequiresBroadcast,
+    /// [`StallPolicy::MaxLag`] must allow at least one value and less than the capacity minus one,
+    /// the most a receiver can fall behind
+    #[error("nexusq channel max lag must satisfy 0 < max lag < capacity - 1")]
+    InvalidMaxLag,
+}
+
+/// How the values sent to a channel are delivered to its receivers
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum DeliveryMode {
+    /// Every receiver receives every value. Values are cloned out of the channel.
+    #[default]
+    Broadcast,
+    /// Each value is received by exactly one receiver. Receivers compete for values and the value
+    /// is moved out of the channel rather than cloned.
+    WorkQueue,
+    /// Every receiver receives every value unless it falls behind. Senders never wait for receivers
+    /// to read a value and instead overwrite the oldest value in the channel. A receiver that falls
+    /// behind gets [`RecvError::Lagged`] with the number of values it missed and then continues from
+    /// the oldest value still in the channel.
+    Lossy,
 }
 
 struct NexusQ<T> {
     buffer: Arc<[cell::Cell<T>]>,
+    // Maps ids to their index in the buffer
+    divisor: Divisor,
+    mode: DeliveryMode,
     write_head: AtomicUsize,
+    // The next id to be claimed by a receiver. Only used in work queue mode
+    read_head: AtomicUsize,
     write_head_wait_strategy: Box<dyn Take<AtomicUsize>>,
     num_receivers: AtomicUsize,
+    num_senders: AtomicUsize,
+    // What senders do when there are no receivers
+    no_receiver_policy: NoReceiverPolicy,
+    // What senders do when the channel is full
+    overflow_policy: OverflowPolicy,
+    // The number of values dropped by the overflow policy
+    dropped: AtomicU64,
+    // The low and high watermarks if the channel has them
+    watermarks: Option<(usize, usize)>,
+    // The last watermark crossed, stored as a Watermark
+    watermark: AtomicUsize,
+    watermark_wait_strategy: Box<dyn Wait<AtomicUsize>>,
+    // When broadcast receivers that hold up the channel are evicted
+    stall_policy: StallPolicy,
+    // The number of consumer groups. Only changed while holding the write head
+    num_groups: AtomicUsize,
+    // Group membership changes are rare so the registry doesn't need to be lock free
+    groups: Mutex<HashMap<Arc<str>, Arc<Group>>>,
+    // Groups whose last member left along with the id their hold on values ends at. The end is set
+    // by the next write head holder when it stops counting them, until then it's None
+    dissolved_groups: Mutex<Vec<(Arc<Group>, Option<usize>)>>,
+    // The number of dissolved groups that are still counted in num_groups
+    num_uncounted_groups: AtomicUsize,
+    // The number of dissolved groups that haven't released every value that counts them
+    num_dissolved_groups: AtomicUsize,
+    // The cursor of every broadcast receiver that isn't in a consumer group. Used to find the last
+    // receiver to read a value so that it can take the value rather than borrow it
+    positions: Mutex<Vec<Arc<Position>>>,
+    // The oldest unread id found the last time the broadcast positions were checked. It's refreshed
+    // while the positions are locked and receivers only move back while holding the lock, so it's
+    // never after the real oldest unread id
+    oldest_unread_hint: AtomicUsize,
+    // Set as soon as the channel is closed. Whoever holds the write head publishes the tombstone
+    closed: AtomicBool,
+    // The id of the tombstone published when the channel was closed. usize::MAX while open
+    closed_at: AtomicUsize,
+    name: Option<Arc<str>>,
+    metrics: Option<Arc<dyn Metrics>>,
 }
 
 impl<T> Debug for NexusQ<T>
@@ -92,21 +185,39 @@ where
         //write all members of nexusq except for the tail_wait_strategy
         f.debug_struct("NexusQ")
             .field("buffer", &self.buffer)
+            .field("divisor", &self.divisor)
+            .field("mode", &self.mode)
             .field("tail", &self.write_head)
+            .field("read_head", &self.read_head)
             .field("num_receivers", &self.num_receivers)
-            .finish()
+            .field("num_senders", &self.num_senders)
+            .field("no_receiver_policy", &self.no_receiver_policy)
+            .field("overflow_policy", &self.overflow_policy)
+            .field("dropped", &self.dropped)
+            .field("watermarks", &self.watermarks)
+            .field("watermark", &self.watermark)
+            .field("stall_policy", &self.stall_policy)
+            .field("num_groups", &self.num_groups)
+            .field("groups", &self.groups)
+            .field("dissolved_groups", &self.dissolved_groups)
+            .field("num_uncounted_groups", &self.num_uncounted_groups)
+            .field("num_dissolved_groups", &self.num_dissolved_groups)
+            .field("positions", &self.positions)
+            .field("oldest_unread_hint", &self.oldest_unread_hint)
+            .field("closed", &self.closed)
+            .field("closed_at", &self.closed_at)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
     }
 }
 
 impl<T> NexusQ<T> {
-    fn new(size: usize) -> Result<Self, NexusError> {
-        Self::with_strategies(size, HybridWait::default(), HybridWait::default)
-    }
-
     fn with_strategies<W, R>(
         size: usize,
         writer_ws: W,
         reader_ws: impl Fn() -> R,
+        mode: DeliveryMode,
+        exact_capacity: bool,
     ) -> Result<Self, NexusError>
     where
         W: Take<AtomicUsize> + 'static,
@@ -120,22 +231,711 @@ impl<T> NexusQ<T> {
             return Err(NexusError::BufferTooLarge);
         }
 
-        let size = size.maybe_next_power_of_two();
+        let size = if exact_capacity {
+            size
+        } else {
+            size.maybe_next_power_of_two()
+        };
         let mut buffer = Vec::with_capacity(size);
         buffer.resize_with(size, || cell::Cell::new(reader_ws()));
         let buffer = Arc::from(buffer.into_boxed_slice());
 
         Ok(Self {
             buffer,
+            divisor: Divisor::new(size as u64),
+            mode,
             write_head: AtomicUsize::new(1),
+            read_head: AtomicUsize::new(1),
             write_head_wait_strategy: Box::new(writer_ws),
             num_receivers: AtomicUsize::new(0),
+            num_senders: AtomicUsize::new(0),
+            no_receiver_policy: NoReceiverPolicy::default(),
+            overflow_policy: OverflowPolicy::default(),
+            dropped: AtomicU64::new(0),
+            watermarks: None,
+            watermark: AtomicUsize::new(Watermark::Low as usize),
+            watermark_wait_strategy: Box::new(reader_ws()),
+            stall_policy: StallPolicy::default(),
+            num_groups: AtomicUsize::new(0),
+            groups: Mutex::default(),
+            dissolved_groups: Mutex::default(),
+            num_uncounted_groups: AtomicUsize::new(0),
+            num_dissolved_groups: AtomicUsize::new(0),
+            positions: Mutex::default(),
+            oldest_unread_hint: AtomicUsize::new(1),
+            closed: AtomicBool::new(false),
+            closed_at: AtomicUsize::new(usize::MAX),
+            name: None,
+            metrics: None,
+        })
+    }
+
+    fn record_sent(&self, id: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.sent(sequence_number(id));
+        }
+        self.update_watermark_after_send();
+        self.check_lag(id);
+        if self.mode == DeliveryMode::Broadcast {
+            // the value may be the last one a dissolved group is waiting on
+            portable_atomic::fence(Ordering::SeqCst);
+            if self.num_dissolved_groups.load(Ordering::Relaxed) > 0 {
+                self.release_dissolved(None);
+            }
+        }
+    }
+
+    fn record_received(&self, id: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.received(sequence_number(id));
+        }
+    }
+
+    fn record_lagged(&self, missed: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.lagged(missed);
+        }
+    }
+
+    /// Check the occupancy against the watermarks and wake anybody waiting for the watermark if it
+    /// was crossed. Called after the occupancy has changed. The check is repeated after every
+    /// crossing as a sender and a receiver racing to update the watermark could otherwise leave
+    /// it stuck on the wrong side.
+    fn update_watermark(&self) {
+        let Some((low, high)) = self.watermarks else {
+            return;
+        };
+        // lossy channels are never full so there's nothing to hold back
+        if self.mode == DeliveryMode::Lossy {
+            return;
+        }
+        loop {
+            portable_atomic::fence(Ordering::SeqCst);
+            let current = Watermark::from_state(self.watermark.load(Ordering::Acquire));
+            let len = self.len();
+            let next = match current {
+                Watermark::Low if len >= high => Watermark::High,
+                Watermark::High if len <= low => Watermark::Low,
+                _ => return,
+            };
+            if self
+                .watermark
+                .compare_exchange(
+                    current as usize,
+                    next as usize,
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                self.watermark_wait_strategy.notify_all();
+                if let Some(metrics) = &self.metrics {
+                    metrics.watermark(next);
+                }
+            }
+        }
+    }
+
+    /// Check the watermarks after a value was sent. Sending can only raise the occupancy, so the
+    /// full check is skipped unless the values sent since the oldest unread hint could have
+    /// reached the high watermark.
+    fn update_watermark_after_send(&self) {
+        let Some((_, high)) = self.watermarks else {
+            return;
+        };
+        if self.watermark.load(Ordering::Acquire) == Watermark::High as usize {
+            return;
+        }
+        if self.mode == DeliveryMode::Broadcast {
+            let oldest = self.oldest_unread_hint.load(Ordering::Acquire);
+            if self.published_until(oldest).wrapping_sub(oldest) < high {
+                return;
+            }
+        }
+        self.update_watermark();
+    }
+
+    /// Check the watermarks after a receiver moved its cursor or read head on to `read`. Receiving
+    /// can only lower the occupancy and every value from `read` on is still in the channel, so the
+    /// full check is skipped unless those values could be at or below the low watermark.
+    fn update_watermark_after_recv(&self, read: usize) {
+        let Some((low, _)) = self.watermarks else {
+            return;
+        };
+        if self.watermark.load(Ordering::Acquire) == Watermark::Low as usize
+            || self.published_until(read).wrapping_sub(read) > low
+        {
+            return;
+        }
+        self.update_watermark();
+    }
+
+    /// Returns true if senders can evict broadcast receivers or move them on. Receivers must be
+    /// activated before they use the channel so that senders leave them alone while they do.
+    fn moves_receivers(&self) -> bool {
+        self.stall_policy != StallPolicy::Never
+            || self.overflow_policy == OverflowPolicy::DropOldest
+    }
+
+    fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Called by a sender holding the write head when the cell for `id` isn't safe to write to.
+    /// Returns true if the sender should wait for the cell, or false if the overflow policy
+    /// handles the value instead.
+    fn make_room(&self, id: usize) -> bool {
+        match self.overflow_policy {
+            OverflowPolicy::Block => true,
+            OverflowPolicy::DropOldest => {
+                self.evict(id.wrapping_sub(self.buffer.len()));
+                true
+            }
+            OverflowPolicy::DropNewest | OverflowPolicy::Reject => false,
+        }
+    }
+
+    /// Like [`NexusQ::make_room`] but for senders that can't wait. Returns true if the cell for `id`
+    /// is safe to write to now.
+    fn try_make_room(&self, id: usize) -> bool {
+        let cell = unsafe { self.buffer.get_unchecked(self.index(id)) };
+        cell.safe_to_write() || (self.make_room(id) && cell.safe_to_write())
+    }
+
+    /// Drop the value published with `id` to make room for a new value. Work queue values and
+    /// values that a consumer group hasn't claimed are claimed from the read head as a receiver
+    /// would and released without being read. Broadcast receivers holding the cell are moved on
+    /// past the next value, see [`NexusQ::move_on_from`]. Receivers that are reading the value
+    /// are left alone, in which case the sender waits for them to finish with the cell.
+    fn evict(&self, id: usize) {
+        let evicted = match self.mode {
+            DeliveryMode::WorkQueue => self.evict_claim(&self.read_head, id),
+            DeliveryMode::Broadcast => {
+                let groups = self.groups.lock().expect("group registry was poisoned");
+                let evicted_groups = groups
+                    .values()
+                    .filter(|group| self.evict_claim(&group.read_head, id))
+                    .count();
+                drop(groups);
+                self.move_on_from(id.wrapping_add(1)) || evicted_groups > 0
+            }
+            DeliveryMode::Lossy => false,
+        };
+        if evicted {
+            self.record_dropped();
+        }
+    }
+
+    /// Claim `id` from `read_head` and release it. Returns false if a receiver claimed it first.
+    fn evict_claim(&self, read_head: &AtomicUsize, id: usize) -> bool {
+        let cell = unsafe { self.buffer.get_unchecked(self.index(id)) };
+        let claimed = cell.get_published() == id
+            && read_head
+                .compare_exchange(id, id.wrapping_add(1), Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok();
+        if claimed {
+            cell.release();
+        }
+        claimed
+    }
+
+    /// Move every idle broadcast receiver with its cursor at `cursor` on to the next id. Their hold
+    /// moves with them and they get [`RecvError::Lagged`] the next time they're used. Returns true
+    /// if any receiver was moved.
+    fn move_on_from(&self, cursor: usize) -> bool {
+        let held = unsafe { self.buffer.get_unchecked(self.index(cursor)) };
+        let released = unsafe {
+            self.buffer
+                .get_unchecked(self.index(cursor.wrapping_sub(1)))
+        };
+        let mut moved = false;
+        for position in self.lock_positions().iter() {
+            moved |= position.move_on_from(cursor, || {
+                held.move_to();
+                released.move_from();
+            });
+        }
+        moved
+    }
+
+    /// Apply the overflow policy to a value that couldn't be sent because the channel is full
+    fn overflow(&self, value: T) -> Result<(), SendError<T>> {
+        if self.overflow_policy == OverflowPolicy::DropNewest {
+            self.record_dropped();
+            return Ok(());
+        }
+        Err(SendError::Full(value))
+    }
+
+    /// The index in the buffer of the cell used by `id`
+    fn index(&self, id: usize) -> usize {
+        id.fast_mod_by(&self.divisor)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Returns the id after the last value that has been published starting from `from`. Ids are
+    /// claimed in order so the end is found with a binary search over the buffer. The result is
+    /// approximate while values are being written. The tombstone isn't counted as a value.
+    fn published_until(&self, from: usize) -> usize {
+        let end = from
+            .saturating_add(self.buffer.len())
+            .min(self.closed_at.load(Ordering::Acquire));
+        let (mut low, mut high) = (from, end.max(from));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let cell = unsafe { self.buffer.get_unchecked(self.index(mid)) };
+            if cell.get_published() >= mid {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// The id of the oldest value that hasn't been read by every receiver. Returns None if there
+    /// is nothing reading from the channel. Lossy receivers aren't tracked.
+    fn oldest_unread(&self) -> Option<usize> {
+        match self.mode {
+            DeliveryMode::WorkQueue => (self.num_receivers.load(Ordering::Relaxed) > 0)
+                .then(|| self.read_head.load(Ordering::Acquire)),
+            DeliveryMode::Broadcast => {
+                let groups = self.groups.lock().expect("group registry was poisoned");
+                let oldest_group = groups
+                    .values()
+                    .map(|group| group.read_head.load(Ordering::Acquire))
+                    .min();
+                drop(groups);
+                let positions = self.lock_positions();
+                let oldest_receiver = positions
+                    .iter()
+                    .map(|position| position.cursor.load(Ordering::Acquire))
+                    .min();
+                let oldest = oldest_group.into_iter().chain(oldest_receiver).min();
+                // receivers that join later start after every value that has been published
+                let hint = oldest.unwrap_or_else(|| {
+                    self.published_until(self.oldest_unread_hint.load(Ordering::Acquire))
+                });
+                self.oldest_unread_hint.store(hint, Ordering::Release);
+                drop(positions);
+                oldest
+            }
+            DeliveryMode::Lossy => None,
+        }
+    }
+
+    /// The id of the latest value published to a lossy channel. Lossy senders don't wait for
+    /// receivers so values aren't published in order and every cell has to be checked. Returns 0
+    /// if nothing has been published.
+    fn latest_lossy(&self) -> usize {
+        self.buffer
+            .iter()
+            .map(cell::Cell::get_published)
+            .max()
+            .unwrap_or_default()
+            .min(self.closed_at.load(Ordering::Acquire).wrapping_sub(1))
+    }
+
+    /// The number of values in the channel that haven't been read by every receiver
+    fn len(&self) -> usize {
+        if self.mode == DeliveryMode::Lossy {
+            // lossy channels always hold the latest values sent to them. Ids start at 1 so the
+            // latest id is the number of values sent
+            return self.latest_lossy().min(self.buffer.len());
+        }
+        self.oldest_unread().map_or(0, |oldest| {
+            self.published_until(oldest).wrapping_sub(oldest)
+        })
+    }
+
+    /// Returns true if the next value sent would have to wait for a receiver
+    fn is_full(&self) -> bool {
+        if self.mode == DeliveryMode::Lossy {
+            return false;
+        }
+        self.oldest_unread().is_some_and(|oldest| {
+            let next = self.published_until(oldest);
+            !unsafe { self.buffer.get_unchecked(self.index(next)) }.safe_to_write()
         })
     }
+
+    /// The number of consumers that must claim each value before its cell can be written to again.
+    /// Broadcast receivers hold their own place in the buffer so don't count as consumers.
+    /// This must be read while holding the write head.
+    fn num_consumers(&self) -> usize {
+        match self.mode {
+            DeliveryMode::Broadcast => self.num_groups.load(Ordering::Relaxed),
+            DeliveryMode::WorkQueue => 1,
+            DeliveryMode::Lossy => 0,
+        }
+    }
+
+    /// Join the named consumer group, creating it if it doesn't exist. A new group receives every
+    /// value sent after it was created.
+    fn join_group(&self, name: &str) -> Arc<Group> {
+        if let Some(group) = self.find_group(name) {
+            return group;
+        }
+        // Hold the write head so that every value from the group's first id counts it as a consumer.
+        // The registry isn't locked while waiting as the holder may be waiting on a group to be
+        // dissolved which requires the registry.
+        let id = self.write_head_wait_strategy.take(&self.write_head);
+        let mut groups = self.groups.lock().expect("group registry was poisoned");
+        // Another receiver may have created the group while waiting for the write head
+        let group = groups.get(name).cloned().map_or_else(
+            || {
+                self.num_groups.fetch_add(1, Ordering::Relaxed);
+                let group = Arc::new(Group::new(name, id));
+                groups.insert(group.name.clone(), group.clone());
+                group
+            },
+            |group| {
+                group.add_member();
+                group
+            },
+        );
+        drop(groups);
+        self.release_write_head(id);
+        group
+    }
+
+    fn find_group(&self, name: &str) -> Option<Arc<Group>> {
+        let groups = self.groups.lock().expect("group registry was poisoned");
+        let group = groups.get(name).cloned();
+        if let Some(group) = &group {
+            group.add_member();
+        }
+        drop(groups);
+        group
+    }
+
+    /// Remove a member from the group. If it was the last member the group is dissolved.
+    fn leave_group(&self, group: &Group) {
+        let mut groups = self.groups.lock().expect("group registry was poisoned");
+        if group.num_members.fetch_sub(1, Ordering::Relaxed) != 1 {
+            return;
+        }
+        let group = groups.remove(&group.name);
+        drop(groups);
+        if let Some(group) = group {
+            self.dissolve_group(group);
+        }
+    }
+
+    /// Release a group with no members from every value it hasn't claimed. Values sent from now on
+    /// stop counting the group as a consumer once the write head is next released. The write head
+    /// isn't waited for as a sender holding it may be waiting on this group, or may be the caller.
+    fn dissolve_group(&self, group: Arc<Group>) {
+        let mut dissolved = self
+            .dissolved_groups
+            .lock()
+            .expect("group registry was poisoned");
+        dissolved.push((group, None));
+        self.num_uncounted_groups.fetch_add(1, Ordering::SeqCst);
+        drop(dissolved);
+        // A sender waiting on a value held by the group can carry on
+        self.release_dissolved(None);
+        // If the write head is held its holder stops counting the group when releasing it
+        if let Some(id) = self.write_head.try_take() {
+            self.release_write_head(id);
+        }
+    }
+
+    /// Release the hold of every dissolved group on the values published so far. Senders call this
+    /// after publishing so that dissolved groups never hold up the channel.
+    ///
+    /// If `end` is given the groups that are still counted as consumers stop being counted. Values
+    /// before `end` were sent while they were counted so the groups keep releasing them as they're
+    /// published. Only the write head holder may give an end.
+    fn release_dissolved(&self, end: Option<usize>) {
+        let mut dissolved = self
+            .dissolved_groups
+            .lock()
+            .expect("group registry was poisoned");
+        if let Some(end) = end {
+            let mut num_ended = 0;
+            for (_, group_end) in dissolved
+                .iter_mut()
+                .filter(|(_, group_end)| group_end.is_none())
+            {
+                *group_end = Some(end);
+                num_ended += 1;
+            }
+            self.num_groups.fetch_sub(num_ended, Ordering::Relaxed);
+            self.num_uncounted_groups
+                .fetch_sub(num_ended, Ordering::SeqCst);
+        }
+        // Senders check the count after publishing so either they see it or the value is seen here
+        self.num_dissolved_groups
+            .store(dissolved.len(), Ordering::Relaxed);
+        portable_atomic::fence(Ordering::SeqCst);
+        // Every value published for a group that is still counted counts it as a consumer
+        dissolved.retain(|(group, group_end)| {
+            let group_end = group_end.unwrap_or(usize::MAX);
+            self.release_published(&group.read_head, group_end) != group_end
+        });
+        self.num_dissolved_groups
+            .store(dissolved.len(), Ordering::Relaxed);
+    }
+
+    /// Release the hold on every published value from `read_head` up to `end` without reading
+    /// them. This must only be used once nobody else can claim from `read_head`.
+    ///
+    /// Returns the first id that wasn't released.
+    fn release_published(&self, read_head: &AtomicUsize, end: usize) -> usize {
+        let mut id = read_head.load(Ordering::Acquire);
+        loop {
+            let cell = unsafe { self.buffer.get_unchecked(self.index(id)) };
+            if id == end || cell.get_published() != id || self.is_tombstone(id) {
+                return id;
+            }
+            read_head.store(id.wrapping_add(1), Ordering::Release);
+            cell.release();
+            self.publish_tombstone();
+            id = id.wrapping_add(1);
+        }
+    }
+
+    /// Start tracking the position of a broadcast receiver with the given cursor
+    fn track_position(&self, cursor: usize) -> Arc<Position> {
+        let position = Arc::new(Position::new(cursor));
+        self.positions
+            .lock()
+            .expect("position registry was poisoned")
+            .push(position.clone());
+        position
+    }
+
+    fn untrack_position(&self, position: &Arc<Position>) {
+        self.positions
+            .lock()
+            .expect("position registry was poisoned")
+            .retain(|other| !Arc::ptr_eq(other, position));
+    }
+
+    /// Lock the positions of the broadcast receivers. Values are only moved out of the channel while
+    /// the positions are locked so that a receiver seeking back can't land on a value that was taken.
+    fn lock_positions(&self) -> MutexGuard<'_, Vec<Arc<Position>>> {
+        self.positions
+            .lock()
+            .expect("position registry was poisoned")
+    }
+
+    /// Returns true if every tracked broadcast receiver other than `own` has finished reading `id`.
+    /// A receiver whose cursor is just past `id` may still be borrowing it.
+    fn others_finished(positions: &[Arc<Position>], own: Option<&Position>, id: usize) -> bool {
+        positions
+            .iter()
+            .filter(|other| !own.is_some_and(|own| core::ptr::eq(Arc::as_ptr(other), own)))
+            .all(|other| other.cursor.load(Ordering::Acquire) > id.wrapping_add(1))
+    }
+
+    /// Wait for the cell for `id` to be safe to write to. See [`NexusQ::wait_for_write_safe_before`].
+    fn wait_for_write_safe(&self, id: usize) -> Result<bool, SendError<()>> {
+        self.wait_for_write_safe_before(id, None)
+    }
+
+    /// Wait for the cell for `id` to be safe to write to, or until the deadline if there is one.
+    /// Receivers that hold up the channel are evicted according to the stall policy while waiting.
+    /// Returns true if the cell was safe to write to straight away.
+    ///
+    /// Returns [`SendError::Disconnected`] if the channel is closed while waiting. The caller still
+    /// holds the write head and must release it.
+    fn wait_for_write_safe_before(
+        &self,
+        id: usize,
+        deadline: Option<Instant>,
+    ) -> Result<bool, SendError<()>> {
+        let cell = unsafe { self.buffer.get_unchecked(self.index(id)) };
+        let closed = || self.is_closed();
+        let result = match self.stall_policy {
+            StallPolicy::Timeout(_) if cell.safe_to_write() => return Ok(true),
+            StallPolicy::Timeout(timeout) => loop {
+                let stalled_at = Instant::now() + timeout;
+                let wait_until = deadline.map_or(stalled_at, |deadline| deadline.min(stalled_at));
+                match cell.wait_for_write_safe_before(wait_until, closed) {
+                    Ok(_) => break Ok(false),
+                    Err(err) if wait_until != stalled_at => break Err(err),
+                    Err(_) => self.evict_before(self.first_unblocking(id)),
+                };
+            },
+            _ => {
+                if matches!(self.stall_policy, StallPolicy::MaxLag(_)) && !cell.safe_to_write() {
+                    // a receiver this far behind should already have been evicted
+                    self.evict_before(self.first_unblocking(id));
+                }
+                deadline.map_or_else(
+                    || Ok(cell.wait_for_write_safe(closed)),
+                    |deadline| cell.wait_for_write_safe_before(deadline, closed),
+                )
+            }
+        };
+        match result {
+            Ok(_) if !cell.safe_to_write() => Err(SendError::Disconnected(None)),
+            Ok(immediate) => Ok(immediate),
+            Err(_) => Err(SendError::Timeout(())),
+        }
+    }
+
+    /// Like [`NexusQ::wait_for_write_safe`] for async senders. Called when the cell for `id` isn't
+    /// safe to write to. Returns true if receivers were evicted and the cell should be checked
+    /// again, otherwise the sender is woken when the stall timeout elapses.
+    fn poll_stalled(&self, id: usize, stall: &mut Option<Deadline>, cx: &Context<'_>) -> bool {
+        match self.stall_policy {
+            StallPolicy::Never => false,
+            StallPolicy::MaxLag(_) => self.evict_before(self.first_unblocking(id)),
+            StallPolicy::Timeout(timeout) => {
+                let deadline = stall.get_or_insert_with(|| Deadline::new(Instant::now() + timeout));
+                if !deadline.poll_elapsed(cx) {
+                    return false;
+                }
+                *stall = None;
+                self.evict_before(self.first_unblocking(id));
+                true
+            }
+        }
+    }
+
+    /// The cursor of the first broadcast receiver that doesn't hold the cell for `id`. Receivers
+    /// hold the cell before their cursor.
+    fn first_unblocking(&self, id: usize) -> usize {
+        id.wrapping_add(2).saturating_sub(self.buffer.len())
+    }
+
+    /// Evict the receivers that have fallen more than the max lag behind after `id` was sent. A
+    /// receiver exactly one value too far behind holds the cell for `id - max_lag - 1` so that is
+    /// the only cell that needs checking.
+    fn check_lag(&self, id: usize) {
+        let StallPolicy::MaxLag(max_lag) = self.stall_policy else {
+            return;
+        };
+        let Some(held) = id.checked_sub(max_lag + 1) else {
+            return;
+        };
+        if unsafe { self.buffer.get_unchecked(self.index(held)) }.is_held() {
+            self.evict_before(id.wrapping_add(1).wrapping_sub(max_lag));
+        }
+    }
+
+    /// Evict every idle broadcast receiver with a cursor before `min_cursor`. Their hold on the
+    /// channel is released and their next operation fails with [`RecvError::Evicted`]. Receivers
+    /// in consumer groups are never evicted. Returns true if any receiver was evicted.
+    fn evict_before(&self, min_cursor: usize) -> bool {
+        let mut evicted = Vec::new();
+        self.lock_positions().retain(|position| {
+            let cursor = position.evict_before(min_cursor);
+            evicted.extend(cursor);
+            cursor.is_none()
+        });
+        for &cursor in &evicted {
+            let cell = unsafe {
+                self.buffer
+                    .get_unchecked(self.index(cursor.wrapping_sub(1)))
+            };
+            cell.move_from();
+            self.num_receivers.sub(1, Ordering::Relaxed);
+            if let Some(metrics) = &self.metrics {
+                metrics.evicted(sequence_number(cursor));
+            }
+        }
+        if evicted.is_empty() {
+            return false;
+        }
+        self.update_watermark();
+        true
+    }
+
+    /// Returns true if `id` is the tombstone that was published when the channel was closed
+    fn is_tombstone(&self, id: usize) -> bool {
+        self.closed_at.load(Ordering::Acquire) == id
+    }
+
+    /// Close the channel. This doesn't wait for the write head. If the write head is currently held
+    /// the holder will finish closing the channel when it releases it.
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        if let Some(id) = self.write_head.try_take() {
+            self.close_with(id);
+        } else {
+            // the holder may be waiting for a full cell so wake it to observe the closed channel
+            self.buffer.iter().for_each(cell::Cell::notify_all);
+        }
+    }
+
+    /// Close the channel using an id that has already been claimed from the write head.
+    /// A tombstone is published for the id which wakes any receivers waiting on it. Receivers that
+    /// are behind will drain the remaining values before reaching the tombstone.
+    ///
+    /// The cell value is not touched so it's safe to publish the tombstone without waiting for broadcast
+    /// receivers to leave the cell. No broadcast receiver can still be waiting on the old value in this cell.
+    fn close_with(&self, id: usize) {
+        self.closed.store(true, Ordering::SeqCst);
+        if self.num_uncounted_groups.load(Ordering::Relaxed) > 0 {
+            self.release_dissolved(Some(id));
+        }
+        if self
+            .closed_at
+            .compare_exchange(usize::MAX, id, Ordering::SeqCst, Ordering::Acquire)
+            .is_ok()
+        {
+            self.publish_tombstone();
+        }
+        self.write_head.restore(id);
+        // wake every sender waiting on the write head so that they can observe the closed channel
+        self.write_head_wait_strategy.notify_all();
+    }
+
+    /// Publish the tombstone of a closed channel.
+    /// Work queue consumers or consumer groups may not have claimed the old value in the tombstone's
+    /// cell yet. If so this does nothing and the consumer that releases it will publish the tombstone instead.
+    fn publish_tombstone(&self) {
+        let id = self.closed_at.load(Ordering::SeqCst);
+        if id == usize::MAX {
+            return;
+        }
+        let cell = unsafe { self.buffer.get_unchecked(self.index(id)) };
+        if !cell.is_released() {
+            return;
+        }
+        cell.publish(id);
+    }
+
+    /// Put `next_id` back into the write head and wake the next sender.
+    /// If the channel was closed while the write head was held the close is finished here.
+    fn release_write_head(&self, next_id: usize) {
+        if self.num_uncounted_groups.load(Ordering::Relaxed) > 0 {
+            self.release_dissolved(Some(next_id));
+        }
+        self.write_head.restore(next_id);
+        portable_atomic::fence(Ordering::SeqCst);
+        if self.closed.load(Ordering::Relaxed) {
+            if let Some(id) = self.write_head.try_take() {
+                self.close_with(id);
+                return;
+            }
+        }
+        // A group dissolved while the write head was being released
+        if self.num_uncounted_groups.load(Ordering::Relaxed) > 0 {
+            if let Some(id) = self.write_head.try_take() {
+                self.release_write_head(id);
+                return;
+            }
+        }
+        self.write_head_wait_strategy.notify_one();
+    }
+}
+
+/// The sequence number of the value sent with `id`. Ids start at 1 as receivers hold the cell before
+/// the next value they'll read.
+const fn sequence_number(id: usize) -> u64 {
+    id.wrapping_sub(1) as u64
 }
 
 /// Create a new nexusq channel with a buffer of the given size.
-/// This function will initialise the channel using the default [`HybridWait`] wait strategies
+/// This function will initialise the channel using the default [`HybridWait`](wait_strategy::hybrid::HybridWait) wait strategies
 /// for both the sender and receiver.
 ///
 /// # Arguments
@@ -152,15 +952,17 @@ impl<T> NexusQ<T> {
 /// let (sender, mut receiver) = nexusq2::make_channel(4).expect("couldn't construct channel");
 /// sender.send(42).expect("couldn't send");
 /// sender.send(2).expect("couldn't send");
-/// assert_eq!(receiver.recv(), 42);
-/// assert_eq!(receiver.recv(), 2);
+/// assert_eq!(receiver.recv(), Ok(42));
+/// assert_eq!(receiver.recv(), Ok(2));
 /// ```
 pub fn make_channel<T>(size: usize) -> Result<(Sender<T>, Receiver<T>), NexusError> {
-    make_channel_with(size, HybridWait::default(), HybridWait::default)
+    ChannelBuilder::new(size).build()
 }
 
 /// Create a new nexusq channel with a buffer of the given size and given wait strategies
 ///
+/// Use [`ChannelBuilder`] to configure any of the other options at the same time.
+///
 /// # Arguments
 ///
 /// * `size`: The size of the channel buffer. This must be at least 2, and no larger than [`isize::MAX`]
@@ -177,7 +979,7 @@ pub fn make_channel<T>(size: usize) -> Result<(Sender<T>, Receiver<T>), NexusErr
 /// use nexusq2::wait_strategy::hybrid::HybridWait;
 /// let (sender, mut receiver) = nexusq2::make_channel_with(4, HybridWait::default(), HybridWait::default).expect("couldn't construct channel");
 /// sender.send(42).expect("couldn't send");
-/// assert_eq!(receiver.recv(), 42);
+/// assert_eq!(receiver.recv(), Ok(42));
 /// ```
 pub fn make_channel_with<T, W, R>(
     size: usize,
@@ -188,10 +990,77 @@ where
     W: Take<AtomicUsize> + 'static,
     R: Wait<AtomicUsize> + 'static + Clone,
 {
-    let nexus = Arc::new(NexusQ::with_strategies(size, writer_ws, reader_ws)?);
-    let receiver = Receiver::new(nexus.clone());
-    let sender = Sender::new(nexus);
-    Ok((sender, receiver))
+    ChannelBuilder::new(size)
+        .writer_wait_strategy(writer_ws)
+        .reader_wait_strategy(reader_ws)
+        .build()
+}
+
+/// Create a new nexusq channel with a buffer of the given size that delivers values using the given
+/// [`DeliveryMode`].
+///
+/// This function will initialise the channel using the default [`HybridWait`](wait_strategy::hybrid::HybridWait) wait strategies
+/// for both the sender and receiver.
+///
+/// # Arguments
+///
+/// * `size`: The size of the channel buffer. This must be at least 2, and no larger than [`isize::MAX`]
+/// * `mode`: How values are delivered to the receivers
+///
+/// # Errors
+/// - [`NexusError::BufferTooSmall`] if the buffer size is less than 2
+/// - [`NexusError::BufferTooLarge`] if the buffer size is larger than [`isize::MAX`]
+///
+/// # Examples
+///
+/// ```rust
+/// use nexusq2::DeliveryMode;
+/// let (sender, mut receiver_a) = nexusq2::make_channel_with_mode(4, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+/// let mut receiver_b = receiver_a.clone();
+/// sender.send(42).expect("couldn't send");
+/// sender.send(2).expect("couldn't send");
+/// assert_eq!(receiver_b.recv(), Ok(42));
+/// assert_eq!(receiver_a.recv(), Ok(2));
+/// assert!(receiver_b.try_recv().is_err());
+/// ```
+pub fn make_channel_with_mode<T>(
+    size: usize,
+    mode: DeliveryMode,
+) -> Result<(Sender<T>, Receiver<T>), NexusError> {
+    ChannelBuilder::new(size).delivery_mode(mode).build()
+}
+
+/// Create a new nexusq channel with a buffer of the given size that has no receivers yet.
+///
+/// Receivers can be created later using [`Sender::subscribe`]. `policy` decides what happens to
+/// values that are sent while there are no receivers.
+///
+/// This function will initialise the channel using the default [`HybridWait`](wait_strategy::hybrid::HybridWait) wait strategies
+/// for both the sender and receiver.
+///
+/// # Arguments
+///
+/// * `size`: The size of the channel buffer. This must be at least 2, and no larger than [`isize::MAX`]
+/// * `policy`: What sends do while there are no receivers
+///
+/// # Errors
+/// - [`NexusError::BufferTooSmall`] if the buffer size is less than 2
+/// - [`NexusError::BufferTooLarge`] if the buffer size is larger than [`isize::MAX`]
+///
+/// # Examples
+///
+/// ```rust
+/// use nexusq2::NoReceiverPolicy;
+/// let sender = nexusq2::make_sender(4, NoReceiverPolicy::Discard).expect("couldn't construct channel");
+/// sender.send(1).expect("couldn't send");
+/// let mut receiver = sender.subscribe();
+/// sender.send(2).expect("couldn't send");
+/// assert_eq!(receiver.recv(), Ok(2));
+/// ```
+pub fn make_sender<T>(size: usize, policy: NoReceiverPolicy) -> Result<Sender<T>, NexusError> {
+    ChannelBuilder::new(size)
+        .no_receiver_policy(policy)
+        .build_sender()
 }
 
 #[cfg(test)]
@@ -207,15 +1076,72 @@ mod tests {
         sender.send(1).expect("couldn't send");
         sender.send(2).expect("couldn't send");
         sender.send(3).expect("couldn't send");
-        assert_eq!(receiver.recv(), 1);
-        assert_eq!(receiver.recv(), 2);
-        assert_eq!(receiver.recv(), 3);
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.recv(), Ok(3));
         sender.send(4).expect("couldn't send");
         sender.send(5).expect("couldn't send");
         sender.send(6).expect("couldn't send");
-        assert_eq!(receiver.recv(), 4);
-        assert_eq!(receiver.recv(), 5);
-        assert_eq!(receiver.recv(), 6);
+        assert_eq!(receiver.recv(), Ok(4));
+        assert_eq!(receiver.recv(), Ok(5));
+        assert_eq!(receiver.recv(), Ok(6));
+    }
+
+    #[test]
+    fn reader_wait_strategy_only_waits_on_atomic_usize() {
+        use std::pin::Pin;
+        use std::task::Poll;
+
+        // A custom strategy that can only wait for exact values
+        #[derive(Clone, Default)]
+        struct ExactWait(wait_strategy::hybrid::HybridWait);
+        impl wait_strategy::Notifiable for ExactWait {
+            fn notify_all(&self) {
+                self.0.notify_all();
+            }
+            fn notify_one(&self) {
+                self.0.notify_one();
+            }
+        }
+        impl Wait<AtomicUsize> for ExactWait {
+            fn wait_for(&self, waitable: &AtomicUsize, expected_value: &usize) {
+                self.0.wait_for(waitable, expected_value);
+            }
+            fn wait_until(
+                &self,
+                waitable: &AtomicUsize,
+                expected_value: &usize,
+                deadline: Instant,
+            ) -> Result<(), wait_strategy::WaitError> {
+                self.0.wait_until(waitable, expected_value, deadline)
+            }
+            fn poll(
+                &self,
+                cx: &mut Context<'_>,
+                waitable: &AtomicUsize,
+                expected_value: &usize,
+                event_listener: &mut Option<Pin<Box<dyn wait_strategy::AsyncEventGuard>>>,
+            ) -> Poll<()> {
+                Wait::poll(&self.0, cx, waitable, expected_value, event_listener)
+            }
+        }
+
+        let (sender, mut receiver) = make_channel_with(
+            4,
+            wait_strategy::hybrid::HybridWait::default(),
+            ExactWait::default,
+        )
+        .expect("couldn't construct channel");
+        let handle = std::thread::spawn(move || {
+            (0..100)
+                .map(|_| receiver.recv().expect("couldn't receive"))
+                .collect::<Vec<_>>()
+        });
+        (0..100).for_each(|i| sender.send(i).expect("couldn't send"));
+        assert_eq!(
+            handle.join().expect("receiver panicked"),
+            (0..100).collect::<Vec<_>>()
+        );
     }
 
     #[tokio::test]
@@ -254,15 +1180,15 @@ mod tests {
         sender.try_send(1).unwrap();
         sender.try_send(2).unwrap();
         sender.try_send(3).unwrap();
-        assert_eq!(receiver.recv(), 1);
-        assert_eq!(receiver.recv(), 2);
-        assert_eq!(receiver.recv(), 3);
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.recv(), Ok(3));
         sender.try_send(4).unwrap();
         sender.try_send(5).unwrap();
         sender.try_send(6).unwrap();
-        assert_eq!(receiver.recv(), 4);
-        assert_eq!(receiver.recv(), 5);
-        assert_eq!(receiver.recv(), 6);
+        assert_eq!(receiver.recv(), Ok(4));
+        assert_eq!(receiver.recv(), Ok(5));
+        assert_eq!(receiver.recv(), Ok(6));
     }
 
     #[test]
@@ -270,39 +1196,1859 @@ mod tests {
         let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
         sender.send(1).expect("couldn't send");
         sender.send(2).expect("couldn't send");
-        assert_eq!(receiver.recv(), 1);
+        assert_eq!(receiver.recv(), Ok(1));
         drop(receiver);
         assert_eq!(sender.send(1), Err(SendError::Disconnected(Some(1))));
     }
 
     #[test]
-    fn buffer_min_size() {
+    fn recv_disconnected_without_senders() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let sender_b = sender.clone();
+        sender.send(1).expect("couldn't send");
+        sender_b.send(2).expect("couldn't send");
+        drop(sender);
+        sender_b.send(3).expect("couldn't send");
+        drop(sender_b);
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv_until(std::time::Instant::now()), Ok(3));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+        assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+        let mut results = Vec::new();
+        assert_eq!(receiver.try_recv_batch(4, &mut results), 0);
+        // new senders can't re-open the channel
+        let sender = receiver.new_sender();
+        assert_eq!(sender.send(4), Err(SendError::Disconnected(Some(4))));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn blocked_recv_wakes_on_disconnect() {
+        let (sender, mut receiver) = make_channel::<usize>(4).expect("couldn't construct channel");
+        let handle = std::thread::spawn(move || receiver.recv());
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        drop(sender);
         assert_eq!(
-            make_channel::<()>(0).unwrap_err(),
-            NexusError::BufferTooSmall
+            handle.join().expect("couldn't join thread"),
+            Err(RecvError::Disconnected)
         );
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn stream_ends_on_disconnect() {
+        let (mut sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        SinkExt::send(&mut sender, 1)
+            .await
+            .expect("couldn't send async");
+        let handle = tokio::spawn(async move {
+            let mut results = Vec::new();
+            while let Some(v) = receiver.next().await {
+                results.push(v);
+            }
+            results
+        });
+        SinkExt::send(&mut sender, 2)
+            .await
+            .expect("couldn't send async");
+        drop(sender);
+        assert_eq!(handle.await.expect("couldn't join task"), vec![1, 2]);
+    }
+
+    #[test]
+    fn close_wakes_blocked_receiver() {
+        let (sender, mut receiver) = make_channel::<usize>(4).expect("couldn't construct channel");
+        let closer = receiver.closer();
+        let handle = std::thread::spawn(move || receiver.recv());
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        closer.close();
         assert_eq!(
-            make_channel::<()>(1).unwrap_err(),
-            NexusError::BufferTooSmall
+            handle.join().expect("couldn't join thread"),
+            Err(RecvError::Disconnected)
         );
-        assert!(make_channel::<()>(2).is_ok());
+        assert!(sender.is_closed());
+        assert_eq!(sender.try_send(1), Err(SendError::Disconnected(Some(1))));
     }
 
     #[test]
-    fn buffer_too_big() {
+    fn close_with_blocked_senders() {
+        let (sender, mut receiver) = make_channel::<usize>(2).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        // this sender holds the write head while it waits for the receiver
+        let sender_a = sender.clone();
+        let holding = std::thread::spawn(move || sender_a.send(2));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        // this sender is waiting on the write head
+        let sender_b = sender.clone();
+        let waiting = std::thread::spawn(move || sender_b.send(3));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!holding.is_finished());
+        assert!(!waiting.is_finished());
+
+        // both senders give up before the receiver frees up the cell
+        sender.close();
         assert_eq!(
-            make_channel::<()>(isize::MAX as usize + 1).unwrap_err(),
-            NexusError::BufferTooLarge
+            holding.join().expect("couldn't join thread"),
+            Err(SendError::Disconnected(Some(2)))
         );
-        //Don't actually test a valid but extremely large buffer as that would kill ram for tests...
+        assert_eq!(
+            waiting.join().expect("couldn't join thread"),
+            Err(SendError::Disconnected(Some(3)))
+        );
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
     }
-}
 
-#[cfg(test)]
-mod drop_tests {
-    use super::*;
-    use portable_atomic::{AtomicU64, Ordering};
-    use pretty_assertions_sorted::assert_eq;
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn sink_close() {
+        let (mut sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let mut sender_b = sender.clone();
+        SinkExt::send(&mut sender, 1)
+            .await
+            .expect("couldn't send async");
+        SinkExt::close(&mut sender)
+            .await
+            .expect("couldn't close async");
+        assert!(!sender_b.is_closed());
+        SinkExt::send(&mut sender_b, 2)
+            .await
+            .expect("couldn't send async");
+        drop((sender, sender_b));
+        assert_eq!(receiver.next().await, Some(1));
+        assert_eq!(receiver.next().await, Some(2));
+        assert_eq!(receiver.next().await, None);
+    }
+
+    #[test]
+    fn close_wakes_blocked_senders() {
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        let blocked = sender.clone();
+        let handle = std::thread::spawn(move || blocked.send(2));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        sender.close();
+        assert_eq!(
+            handle.join().expect("sender panicked"),
+            Err(SendError::Disconnected(Some(2)))
+        );
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn close_wakes_blocked_async_senders() {
+        let (sender, _receiver) = make_channel(2).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        let blocked = sender.clone();
+        let handle = tokio::spawn(async move { blocked.send_async(2).await });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!handle.is_finished());
+        sender.close();
+        assert_eq!(
+            handle.await.expect("sender panicked"),
+            Err(SendError::Disconnected(Some(2)))
+        );
+    }
+
+    #[test]
+    fn work_queue_delivers_once() {
+        let (sender, receiver) = make_channel_with_mode::<usize>(4, DeliveryMode::WorkQueue)
+            .expect("couldn't construct channel");
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                let mut receiver = receiver.clone();
+                std::thread::spawn(move || {
+                    let mut results = Vec::new();
+                    while let Ok(v) = receiver.recv() {
+                        results.push(v);
+                    }
+                    results
+                })
+            })
+            .collect();
+        drop(receiver);
+        for i in 0..1000 {
+            sender.send(i).expect("couldn't send");
+        }
+        drop(sender);
+        let mut results: Vec<_> = handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("couldn't join thread"))
+            .collect();
+        results.sort_unstable();
+        assert_eq!(results, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn work_queue_close_drains() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+        let mut receiver_b = receiver.clone();
+        sender.send(1).expect("couldn't send");
+        sender.send(2).expect("couldn't send");
+        sender.send(3).expect("couldn't send");
+        sender.close();
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver_b.recv(), Ok(2));
+        assert_eq!(receiver.try_recv_until(std::time::Instant::now()), Ok(3));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+        assert_eq!(receiver_b.try_recv(), Err(RecvError::Disconnected));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn work_queue_async() {
+        let (mut sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+        let mut receiver_b = receiver.clone();
+        SinkExt::send(&mut sender, 1)
+            .await
+            .expect("couldn't send async");
+        SinkExt::send(&mut sender, 2)
+            .await
+            .expect("couldn't send async");
+        assert_eq!(receiver_b.next().await, Some(1));
+        assert_eq!(receiver.next().await, Some(2));
+        drop(sender);
+        assert_eq!(receiver.next().await, None);
+        assert_eq!(receiver_b.next().await, None);
+    }
+
+    #[test]
+    fn consumer_groups() {
+        let (sender, receiver) = make_channel::<usize>(4).expect("couldn't construct channel");
+        let auditor = receiver
+            .join_group("auditors")
+            .expect("couldn't join group");
+        let worker = receiver.join_group("workers").expect("couldn't join group");
+        drop(receiver);
+        let spawn = |mut receiver: Receiver<usize>| {
+            std::thread::spawn(move || {
+                let mut results = Vec::new();
+                while let Ok(v) = receiver.recv() {
+                    results.push(v);
+                }
+                results
+            })
+        };
+        let auditor = spawn(auditor);
+        let workers: Vec<_> = (0..3).map(|_| spawn(worker.clone())).collect();
+        drop(worker);
+        for i in 0..1000 {
+            sender.send(i).expect("couldn't send");
+        }
+        drop(sender);
+        let expected: Vec<_> = (0..1000).collect();
+        assert_eq!(auditor.join().expect("couldn't join thread"), expected);
+        let mut results: Vec<_> = workers
+            .into_iter()
+            .flat_map(|h| h.join().expect("couldn't join thread"))
+            .collect();
+        results.sort_unstable();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn dropped_group_releases_values() {
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        let group = receiver.join_group("group").expect("couldn't join group");
+        sender.send(1).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(1));
+        sender.send(2).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(2));
+        // the group is holding both values
+        assert_eq!(sender.try_send(3), Err(SendError::Full(3)));
+        drop(group);
+        sender.send(3).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(3));
+        // a new group only receives new values
+        let mut group = receiver.join_group("group").expect("couldn't join group");
+        sender.send(4).expect("couldn't send");
+        assert_eq!(group.recv(), Ok(4));
+        assert_eq!(group.try_recv(), Err(RecvError::NoNewData));
+    }
+
+    #[test]
+    fn dropped_group_while_holding_write_head() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let group = receiver.join_group("group").expect("couldn't join group");
+        sender.send(1).expect("couldn't send");
+        let permit = sender.reserve().expect("couldn't reserve");
+        // the group is dissolved without waiting for the write head
+        drop(group);
+        permit.send(2);
+        for value in 3..=6 {
+            assert_eq!(receiver.recv(), Ok(value - 2));
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(receiver.recv(), Ok(5));
+        assert_eq!(receiver.recv(), Ok(6));
+    }
+
+    #[test]
+    fn groups_require_broadcast() {
+        let (_sender, receiver) = make_channel_with_mode::<usize>(4, DeliveryMode::WorkQueue)
+            .expect("couldn't construct channel");
+        assert_eq!(
+            receiver.join_group("group").unwrap_err(),
+            NexusError::GroupsRequireBroadcast
+        );
+    }
+
+    #[test]
+    fn lossy_receiver_lags() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::Lossy).expect("couldn't construct channel");
+        for i in 1..=10 {
+            sender
+                .try_send(i)
+                .expect("lossy sends shouldn't wait for receivers");
+        }
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(6)));
+        let mut results = Vec::new();
+        assert_eq!(receiver.try_recv_batch(4, &mut results), 3);
+        assert_eq!(results, vec![7, 8, 9]);
+        assert_eq!(receiver.try_recv(), Ok(10));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+        sender.send(11).expect("couldn't send");
+        drop(sender);
+        assert_eq!(receiver.recv(), Ok(11));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn lossy_slow_receiver() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(8, DeliveryMode::Lossy).expect("couldn't construct channel");
+        let handle = std::thread::spawn(move || {
+            let mut previous = None;
+            let mut num_read = 0;
+            loop {
+                match receiver.recv() {
+                    Ok(v) => {
+                        assert!(previous < Some(v));
+                        previous = Some(v);
+                        num_read += 1;
+                    }
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(_) => return num_read,
+                }
+            }
+        });
+        for i in 0..100_000 {
+            sender.send(i).expect("couldn't send");
+        }
+        drop(sender);
+        assert!(handle.join().expect("couldn't join thread") > 0);
+    }
+
+    #[test]
+    fn late_subscriber() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        sender.send(0).expect("couldn't send");
+        sender.send(1).expect("couldn't send");
+        let mut late = sender.subscribe();
+        assert_eq!(late.try_recv(), Err(RecvError::NoNewData));
+        sender.send(2).expect("couldn't send");
+        for i in 0..3 {
+            assert_eq!(receiver.recv(), Ok(i));
+        }
+        // the late subscriber holds its place so senders must wait for it to read
+        sender.try_send(3).expect("couldn't send");
+        sender.try_send(4).expect("couldn't send");
+        assert_eq!(sender.try_send(5), Err(SendError::Full(5)));
+        assert_eq!(late.recv(), Ok(2));
+        sender.try_send(5).expect("couldn't send");
+        drop(sender);
+        for i in 3..6 {
+            assert_eq!(late.recv(), Ok(i));
+        }
+        assert_eq!(late.recv(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn sender_without_receivers() {
+        let sender =
+            make_sender(4, NoReceiverPolicy::Disconnect).expect("couldn't construct channel");
+        assert_eq!(sender.send(1), Err(SendError::Disconnected(Some(1))));
+        assert_eq!(sender.try_send(1), Err(SendError::Disconnected(Some(1))));
+        let mut receiver = sender.subscribe();
+        sender.send(2).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(2));
+        drop(receiver);
+        assert_eq!(sender.send(3), Err(SendError::Disconnected(Some(3))));
+
+        let sender = make_sender(4, NoReceiverPolicy::Discard).expect("couldn't construct channel");
+        for i in 0..10 {
+            sender.send(i).expect("couldn't send");
+            sender.try_send(i).expect("couldn't send");
+        }
+        let mut receiver = sender.subscribe();
+        sender.send(10).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(10));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn sink_discards_without_receivers() {
+        let mut sender =
+            make_sender(4, NoReceiverPolicy::Discard).expect("couldn't construct channel");
+        SinkExt::send(&mut sender, 1).await.expect("couldn't send");
+        let mut receiver = sender.subscribe();
+        SinkExt::send(&mut sender, 2).await.expect("couldn't send");
+        drop(sender);
+        assert_eq!(receiver.next().await, Some(2));
+        assert_eq!(receiver.next().await, None);
+    }
+
+    #[test]
+    fn recv_ref_without_clone() {
+        #[derive(Debug, PartialEq)]
+        struct NotClone(usize);
+
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let mut receiver_b = sender.subscribe();
+        for i in 0..3 {
+            sender.send(NotClone(i)).expect("couldn't send");
+        }
+        for i in 0..3 {
+            assert_eq!(*receiver.recv_ref().expect("couldn't receive"), NotClone(i));
+            assert_eq!(receiver_b.recv_with(|value| value.0), Ok(i));
+        }
+        drop(sender);
+        assert!(matches!(receiver.recv_ref(), Err(RecvError::Disconnected)));
+        assert_eq!(
+            receiver_b.recv_with(|value| value.0),
+            Err(RecvError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn recv_ref_releases_claim() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(2, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        sender.send(2).expect("couldn't send");
+        let value = receiver.recv_ref().expect("couldn't receive");
+        assert_eq!(*value, 1);
+        // the claimed value can't be overwritten until the borrow is dropped
+        assert_eq!(sender.try_send(3), Err(SendError::Full(3)));
+        drop(value);
+        sender.try_send(3).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.recv_with(|value| *value), Ok(3));
+    }
+
+    #[test]
+    fn lossy_recv_ref() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::Lossy).expect("couldn't construct channel");
+        for i in 0..6 {
+            sender.send(i).expect("couldn't send");
+        }
+        assert!(matches!(receiver.recv_ref(), Err(RecvError::Lagged(2))));
+        assert_eq!(receiver.recv_with(|value| *value), Ok(2));
+        assert_eq!(*receiver.recv_ref().expect("couldn't receive"), 3);
+    }
+
+    #[test]
+    fn recv_take_moves_to_last_reader() {
+        let (sender, mut receiver_a) = make_channel::<Box<dyn FnOnce() -> usize + Send>>(4)
+            .expect("couldn't construct channel");
+        let mut receiver_b = receiver_a.clone();
+        for i in 0..3 {
+            sender
+                .send(Box::new(move || i))
+                .unwrap_or_else(|_| panic!("couldn't send"));
+        }
+        for _ in 0..3 {
+            assert!(matches!(receiver_a.recv_take(), Ok(Received::Borrowed(_))));
+        }
+        // receiver a may still be borrowing the last value it read
+        for i in 0..2 {
+            let Ok(Received::Owned(f)) = receiver_b.recv_take() else {
+                panic!("receiver b should have taken the value");
+            };
+            assert_eq!(f(), i);
+        }
+        assert!(matches!(receiver_b.recv_take(), Ok(Received::Borrowed(_))));
+        drop(receiver_a);
+        sender
+            .send(Box::new(|| 3))
+            .unwrap_or_else(|_| panic!("couldn't send"));
+        let Ok(Received::Owned(f)) = receiver_b.recv_take() else {
+            panic!("receiver b should have taken the value");
+        };
+        assert_eq!(f(), 3);
+    }
+
+    #[test]
+    fn recv_take_shared() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+        sender.send(String::from("a")).expect("couldn't send");
+        assert!(matches!(receiver.recv_take(), Ok(Received::Owned(value)) if value == "a"));
+
+        let (sender, receiver) = make_channel(4).expect("couldn't construct channel");
+        let mut group_a = receiver.join_group("a").expect("couldn't join group");
+        let mut group_b = receiver.join_group("b").expect("couldn't join group");
+        let mut receiver = receiver;
+        sender.send(String::from("b")).expect("couldn't send");
+        sender.send(String::from("c")).expect("couldn't send");
+        assert!(matches!(receiver.recv_take(), Ok(Received::Borrowed(_))));
+        assert!(matches!(group_a.recv_take(), Ok(Received::Borrowed(_))));
+        // the broadcast receiver hasn't finished with the value until it reads the next one
+        assert!(matches!(group_b.recv_take(), Ok(Received::Borrowed(_))));
+        assert!(matches!(receiver.recv_take(), Ok(Received::Borrowed(_))));
+        assert!(matches!(group_a.recv_take(), Ok(Received::Borrowed(_))));
+        drop(receiver);
+        assert!(matches!(group_b.recv_take(), Ok(Received::Owned(value)) if value == "c"));
+    }
+
+    #[test]
+    fn batch_send() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let handle = std::thread::spawn(move || {
+            let mut results = Vec::new();
+            while let Ok(value) = receiver.recv() {
+                results.push(value);
+            }
+            results
+        });
+        // batches are longer than the buffer so they're split
+        let sender_b = sender.clone();
+        let handle_b = std::thread::spawn(move || sender_b.send_batch(100..200));
+        assert_eq!(sender.send_batch(0..100), Ok(100));
+        assert_eq!(handle_b.join().expect("couldn't join thread"), Ok(100));
+        drop(sender);
+        let results = handle.join().expect("couldn't join thread");
+        assert_eq!(results.len(), 200);
+        for batch in results.chunks(4) {
+            // every split batch is written to contiguous ids
+            assert!(batch.windows(2).all(|pair| pair[0] + 1 == pair[1]));
+        }
+    }
+
+    #[test]
+    fn batch_send_disconnected() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        sender.send_slice(&[1, 2]).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        drop(receiver);
+        assert_eq!(
+            sender.send_slice(&[3, 4]),
+            Err(SendError::Disconnected(Some(&[3, 4][..])))
+        );
+        let mut values = 5..8;
+        assert_eq!(
+            sender.send_batch(&mut values),
+            Err(SendError::Disconnected(Some(5)))
+        );
+        assert_eq!(values.next(), Some(6));
+
+        // discarded values aren't counted as sent
+        let sender = make_sender(4, NoReceiverPolicy::Discard).expect("couldn't construct channel");
+        let mut values = 0..10;
+        assert_eq!(sender.send_batch(&mut values), Ok(0));
+        assert_eq!(values.next(), None);
+        let mut receiver = sender.subscribe();
+        assert_eq!(sender.send_batch(0..2), Ok(2));
+        assert_eq!(receiver.recv(), Ok(0));
+    }
+
+    #[test]
+    fn blocking_batch_recv() {
+        let (sender, mut receiver) = make_channel(8).expect("couldn't construct channel");
+        let handle = std::thread::spawn(move || {
+            for i in 0..5 {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+                sender.send(i).expect("couldn't send");
+            }
+        });
+        let mut results = Vec::new();
+        assert!(
+            receiver
+                .recv_batch(3, 4, &mut results)
+                .expect("couldn't receive")
+                >= 3
+        );
+        while receiver.recv_batch(3, 4, &mut results).is_ok() {}
+        handle.join().expect("couldn't join thread");
+        assert_eq!(results, vec![0, 1, 2, 3, 4]);
+
+        // a minimum of zero doesn't wait for new values
+        let (sender, mut receiver) = make_channel(8).expect("couldn't construct channel");
+        sender.send(5).expect("couldn't send");
+        assert_eq!(receiver.recv_batch(0, 4, &mut results), Ok(1));
+        assert_eq!(receiver.recv_batch(0, 4, &mut results), Ok(0));
+        assert_eq!(results, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn batch_recv_until() {
+        let (sender, mut receiver) = make_channel(8).expect("couldn't construct channel");
+        let mut results = Vec::new();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(10);
+        assert_eq!(
+            receiver.recv_batch_until(deadline, 1, 4, &mut results),
+            Err(RecvError::Timeout)
+        );
+        sender.send_slice(&[1, 2, 3]).expect("couldn't send");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(10);
+        assert_eq!(
+            receiver.recv_batch_until(deadline, 1, 2, &mut results),
+            Ok(2)
+        );
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(10);
+        assert_eq!(
+            receiver.recv_batch_until(deadline, 2, 2, &mut results),
+            Ok(1)
+        );
+        assert_eq!(results, vec![1, 2, 3]);
+        drop(sender);
+        assert_eq!(
+            receiver.recv_batch_until(deadline, 2, 2, &mut results),
+            Err(RecvError::Disconnected)
+        );
+    }
+
+    /// Keep every core busy sending and receiving on another channel until the returned flag is set
+    fn generate_load() -> (Arc<AtomicBool>, Vec<std::thread::JoinHandle<()>>) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = make_channel(16).expect("couldn't construct channel");
+        let num_threads = std::thread::available_parallelism().map_or(2, usize::from);
+        let mut handles = Vec::new();
+        for i in 0..num_threads {
+            let stop = stop.clone();
+            if i % 2 == 0 {
+                let sender = sender.clone();
+                handles.push(std::thread::spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let _ = sender.try_send(i);
+                    }
+                }));
+            } else {
+                let mut receiver = receiver.clone();
+                handles.push(std::thread::spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let _ = receiver.try_recv();
+                    }
+                }));
+            }
+        }
+        (stop, handles)
+    }
+
+    /// Checks that timed out calls returned close to their timeout. The median catches timeouts
+    /// that consistently fire late while a single slow wake up on a loaded machine is allowed up to
+    /// the ceiling, which only catches timeouts that never fire.
+    fn assert_timeouts_accurate(
+        mut elapsed: Vec<std::time::Duration>,
+        timeout: std::time::Duration,
+    ) {
+        let max_median_error = std::time::Duration::from_millis(20);
+        let max_error = std::time::Duration::from_secs(5);
+        elapsed.sort();
+        assert!(elapsed
+            .iter()
+            .all(|e| *e >= timeout && *e < timeout + max_error));
+        let median = elapsed[elapsed.len() / 2];
+        assert!(
+            median < timeout + max_median_error,
+            "median timeout took {median:?}"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn timeouts_under_load() {
+        let (stop, handles) = generate_load();
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        let timeout = std::time::Duration::from_millis(20);
+        let mut elapsed = Vec::new();
+        for _ in 0..5 {
+            let start = std::time::Instant::now();
+            assert_eq!(receiver.recv_timeout(timeout), Err(RecvError::Timeout));
+            elapsed.push(start.elapsed());
+        }
+        sender.send_timeout(1, timeout).expect("couldn't send");
+        for _ in 0..5 {
+            let start = std::time::Instant::now();
+            assert_eq!(sender.send_timeout(2, timeout), Err(SendError::Timeout(2)));
+            elapsed.push(start.elapsed());
+        }
+        assert_eq!(receiver.recv_timeout(timeout), Ok(1));
+        assert_timeouts_accurate(elapsed, timeout);
+        stop.store(true, Ordering::Relaxed);
+        handles
+            .into_iter()
+            .for_each(|handle| handle.join().expect("couldn't join thread"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[cfg_attr(miri, ignore)]
+    async fn async_timeouts_under_load() {
+        let (stop, handles) = generate_load();
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        let timeout = std::time::Duration::from_millis(20);
+        let mut elapsed = Vec::new();
+        for _ in 0..5 {
+            let start = std::time::Instant::now();
+            assert_eq!(
+                receiver.recv_timeout_async(timeout).await,
+                Err(RecvError::Timeout)
+            );
+            elapsed.push(start.elapsed());
+        }
+        sender
+            .send_timeout_async(1, timeout)
+            .await
+            .expect("couldn't send");
+        for _ in 0..5 {
+            let start = std::time::Instant::now();
+            assert_eq!(
+                sender.send_timeout_async(2, timeout).await,
+                Err(SendError::Timeout(2))
+            );
+            elapsed.push(start.elapsed());
+        }
+        // the timed out sends gave the write head back
+        assert_eq!(receiver.recv_timeout_async(timeout).await, Ok(1));
+        sender
+            .send_timeout_async(2, timeout)
+            .await
+            .expect("couldn't send");
+        assert_eq!(receiver.recv_timeout_async(timeout).await, Ok(2));
+        assert_timeouts_accurate(elapsed, timeout);
+        stop.store(true, Ordering::Relaxed);
+        handles
+            .into_iter()
+            .for_each(|handle| handle.join().expect("couldn't join thread"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shared_sender_async() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let sender = Arc::new(sender);
+        let tasks = (0..4)
+            .map(|task| {
+                let sender = Arc::clone(&sender);
+                tokio::spawn(async move {
+                    for value in 0..100 {
+                        sender
+                            .send_async(task * 100 + value)
+                            .await
+                            .expect("couldn't send");
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut received = Vec::with_capacity(400);
+        for _ in 0..400 {
+            received.push(receiver.recv_async().await.expect("couldn't receive"));
+        }
+        for task in tasks {
+            task.await.expect("task panicked");
+        }
+        received.sort_unstable();
+        assert_eq!(received, (0..400).collect::<Vec<_>>());
+        drop(sender);
+        assert_eq!(receiver.recv_async().await, Err(RecvError::Disconnected));
+    }
+
+    #[tokio::test]
+    async fn dropped_send_future() {
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        // the future claims the write head then waits for the full buffer
+        let mut pending = Box::pin(sender.send_async(2));
+        assert!(futures_util::FutureExt::now_or_never(&mut pending).is_none());
+        drop(pending);
+        assert_eq!(receiver.recv_async().await, Ok(1));
+        sender.send_async(3).await.expect("couldn't send");
+        assert_eq!(receiver.recv_async().await, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn cancelled_send_future() {
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        tokio::select! {
+            _ = sender.send_async(2) => panic!("the channel should be full"),
+            () = tokio::time::sleep(std::time::Duration::from_millis(10)) => {}
+        }
+        let other = sender.clone();
+        let handle = std::thread::spawn(move || other.send(3).expect("couldn't send"));
+        assert_eq!(receiver.recv_async().await, Ok(1));
+        handle.join().expect("couldn't join thread");
+        assert_eq!(receiver.recv_async().await, Ok(3));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn aborted_sink_send() {
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        let mut sink = sender.clone();
+        let task = tokio::spawn(async move {
+            SinkExt::send(&mut sink, 2).await.expect("couldn't send");
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        // dropping the task drops the sender that claimed the write head
+        task.abort();
+        assert!(task.await.is_err_and(|err| err.is_cancelled()));
+        assert_eq!(receiver.recv_async().await, Ok(1));
+        sender.send_async(3).await.expect("couldn't send");
+        assert_eq!(receiver.recv_async().await, Ok(3));
+    }
+
+    #[test]
+    fn sender_dropped_after_poll_ready() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let mut sink = sender.clone();
+        let waker = futures_util::task::noop_waker();
+        let mut cx = std::task::Context::from_waker(&waker);
+        assert!(matches!(
+            futures_util::Sink::poll_ready(core::pin::Pin::new(&mut sink), &mut cx),
+            std::task::Poll::Ready(Ok(()))
+        ));
+        drop(sink);
+        sender.send(1).expect("couldn't send");
+        sender.send(2).expect("couldn't send");
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+    }
+
+    #[test]
+    fn sink_ready_doesnt_hold_the_write_head() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let mut sink = core::pin::pin!(sender.clone());
+        let waker = futures_util::task::noop_waker();
+        let mut cx = std::task::Context::from_waker(&waker);
+        assert!(matches!(
+            futures_util::Sink::poll_ready(sink.as_mut(), &mut cx),
+            std::task::Poll::Ready(Ok(()))
+        ));
+        // other senders aren't held up while the sink waits for its next value
+        assert_eq!(sender.try_send(1), Ok(()));
+        assert_eq!(futures_util::Sink::start_send(sink.as_mut(), 2), Ok(()));
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+        assert!(matches!(
+            futures_util::Sink::poll_flush(sink.as_mut(), &mut cx),
+            std::task::Poll::Ready(Ok(()))
+        ));
+        assert_eq!(receiver.try_recv(), Ok(2));
+    }
+
+    #[test]
+    fn peek_leaves_position() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        assert!(matches!(receiver.try_peek(), Err(RecvError::NoNewData)));
+        sender.send(vec![1]).expect("couldn't send");
+        sender.send(vec![2]).expect("couldn't send");
+        assert_eq!(*receiver.peek().expect("couldn't peek"), vec![1]);
+        assert_eq!(*receiver.try_peek().expect("couldn't peek"), vec![1]);
+        assert_eq!(receiver.recv(), Ok(vec![1]));
+        assert_eq!(*receiver.peek().expect("couldn't peek"), vec![2]);
+        // the peeked value can't be overwritten while it's borrowed
+        let peeked = receiver.try_peek().expect("couldn't peek");
+        sender.send(vec![3]).expect("couldn't send");
+        sender.send(vec![4]).expect("couldn't send");
+        assert!(matches!(sender.try_send(vec![5]), Err(SendError::Full(_))));
+        assert_eq!(*peeked, vec![2]);
+        drop(peeked);
+        assert_eq!(receiver.recv(), Ok(vec![2]));
+        drop(sender);
+        assert_eq!(receiver.recv(), Ok(vec![3]));
+        assert_eq!(receiver.recv(), Ok(vec![4]));
+        assert!(matches!(receiver.peek(), Err(RecvError::Disconnected)));
+
+        let (_, mut receiver) = make_channel_with_mode::<usize>(4, DeliveryMode::WorkQueue)
+            .expect("couldn't construct channel");
+        assert!(matches!(receiver.try_peek(), Err(RecvError::Unsupported)));
+    }
+
+    #[test]
+    fn lossy_peek() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::Lossy).expect("couldn't construct channel");
+        for value in 0..6 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert!(matches!(receiver.peek(), Err(RecvError::Lagged(2))));
+        assert_eq!(*receiver.peek().expect("couldn't peek"), 2);
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(*receiver.try_peek().expect("couldn't peek"), 3);
+        assert_eq!(receiver.recv(), Ok(3));
+    }
+
+    #[tokio::test]
+    async fn peek_async() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            sender.send(1).expect("couldn't send");
+        });
+        assert_eq!(*receiver.peek_async().await.expect("couldn't peek"), 1);
+        assert_eq!(*receiver.peek_async().await.expect("couldn't peek"), 1);
+        assert_eq!(receiver.recv_async().await, Ok(1));
+        handle.join().expect("couldn't join thread");
+        assert!(matches!(
+            receiver.peek_async().await,
+            Err(RecvError::Disconnected)
+        ));
+    }
+
+    #[test]
+    fn skip_and_seek() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        for value in 1..4 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert!(matches!(sender.try_send(4), Err(SendError::Full(_))));
+        assert_eq!(Receiver::skip(&mut receiver, 1), Ok(1));
+        // the skipped value's cell can be written to again
+        sender.try_send(4).expect("couldn't send");
+        assert_eq!(receiver.seek_to_latest(), Ok(3));
+        sender.send(5).expect("couldn't send");
+        sender.send(6).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(5));
+        assert_eq!(receiver.recv(), Ok(6));
+        // the receiver holds 3 so that 4 can be received again
+        assert_eq!(receiver.seek_to_oldest(), Ok(3));
+        assert!(matches!(sender.try_send(7), Err(SendError::Full(_))));
+        assert_eq!(receiver.recv(), Ok(4));
+        assert_eq!(receiver.recv(), Ok(5));
+        drop(sender);
+        assert_eq!(receiver.seek_to_latest(), Ok(1));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+
+        let (_, mut receiver) = make_channel_with_mode::<usize>(4, DeliveryMode::WorkQueue)
+            .expect("couldn't construct channel");
+        assert_eq!(
+            Receiver::skip(&mut receiver, 1),
+            Err(RecvError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn seek_back_stops_at_taken_values() {
+        let (sender, mut receiver_a) = make_channel(8).expect("couldn't construct channel");
+        let mut receiver_b = receiver_a.clone();
+        for value in 1..5 {
+            sender.send(Box::new(value)).expect("couldn't send");
+        }
+        for _ in 0..4 {
+            drop(receiver_a.recv_ref().expect("couldn't receive"));
+        }
+        // receiver b is the last to read the first two values so it takes them
+        assert!(matches!(receiver_b.recv_take(), Ok(Received::Owned(_))));
+        assert!(matches!(receiver_b.recv_take(), Ok(Received::Owned(_))));
+        assert_eq!(receiver_a.seek_to_oldest(), Ok(2));
+        assert_eq!(
+            *receiver_a.recv_ref().expect("couldn't receive"),
+            Box::new(3)
+        );
+        // the values receiver b took can't be received again
+        assert_eq!(receiver_b.seek_to_oldest(), Ok(0));
+    }
+
+    #[test]
+    fn lossy_seek() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::Lossy).expect("couldn't construct channel");
+        for value in 0..6 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(receiver.seek_to_oldest(), Ok(0));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(Receiver::skip(&mut receiver, 2), Ok(2));
+        assert_eq!(receiver.recv(), Ok(5));
+        assert_eq!(receiver.seek_to_oldest(), Ok(4));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.seek_to_latest(), Ok(3));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+    }
+
+    #[test]
+    fn sequence_numbers() {
+        let (sender, mut receiver_a) = make_channel(8).expect("couldn't construct channel");
+        let mut receiver_b = receiver_a.clone();
+        for value in 0..4 {
+            assert_eq!(sender.send_with_seq(value * 10), Ok(Some(value)));
+        }
+        assert_eq!(receiver_a.recv_with_seq(), Ok((0, 0)));
+        assert_eq!(Receiver::skip(&mut receiver_b, 2), Ok(2));
+        assert_eq!(receiver_a.position(), 1);
+        assert_eq!(receiver_b.position(), 2);
+        assert_eq!(receiver_b.recv_with_seq(), Ok((2, 20)));
+        assert_eq!(receiver_a.recv_ref().expect("couldn't receive").seq(), 1);
+        assert_eq!(sender.try_send_with_seq(40), Ok(Some(4)));
+
+        let (sender, mut receiver_a) =
+            make_channel_with_mode(4, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+        let mut receiver_b = receiver_a.clone();
+        sender.send(0).expect("couldn't send");
+        sender.send(10).expect("couldn't send");
+        assert_eq!(receiver_b.recv_with_seq(), Ok((0, 0)));
+        assert_eq!(receiver_a.position(), 1);
+        assert_eq!(receiver_a.recv_with_seq(), Ok((1, 10)));
+        drop((receiver_a, receiver_b));
+        assert_eq!(
+            sender.send_with_seq(20),
+            Err(SendError::Disconnected(Some(20)))
+        );
+
+        let sender = make_sender(4, NoReceiverPolicy::Discard).expect("couldn't construct channel");
+        assert_eq!(sender.send_with_seq(0), Ok(None));
+        let mut receiver = sender.subscribe();
+        assert_eq!(receiver.position(), 0);
+        assert_eq!(sender.send_with_seq(10), Ok(Some(0)));
+        assert_eq!(receiver.recv_with_seq(), Ok((0, 10)));
+    }
+
+    #[test]
+    fn lossy_sequence_gaps() {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::Lossy).expect("couldn't construct channel");
+        for value in 0..6 {
+            assert_eq!(sender.send_with_seq(value), Ok(Some(value)));
+        }
+        assert_eq!(receiver.recv_with_seq(), Err(RecvError::Lagged(2)));
+        assert_eq!(receiver.position(), 2);
+        assert_eq!(receiver.recv_with_seq(), Ok((2, 2)));
+    }
+
+    #[test]
+    fn introspection() {
+        let (sender, mut receiver_a) = make_channel(5).expect("couldn't construct channel");
+        assert_eq!(sender.capacity(), 8);
+        assert_eq!(receiver_a.capacity(), 8);
+        let mut receiver_b = receiver_a.clone();
+        let group = receiver_a.join_group("group").expect("couldn't join group");
+        assert_eq!(sender.receiver_count(), 3);
+        assert_eq!(receiver_a.sender_count(), 1);
+        for value in 0..7 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.len(), 7);
+        assert!(sender.is_full());
+        assert_eq!(receiver_a.pending(), 7);
+        assert_eq!(group.pending(), 7);
+        receiver_a.recv().expect("couldn't receive");
+        receiver_b.recv().expect("couldn't receive");
+        assert_eq!(receiver_a.pending(), 6);
+        // the group hasn't received anything yet but doesn't hold a slot
+        assert_eq!(sender.len(), 7);
+        assert!(!sender.is_full());
+        sender.send(7).expect("couldn't send");
+        assert_eq!(sender.len(), 8);
+        assert!(sender.is_full());
+        drop(group);
+        assert_eq!(sender.len(), 7);
+        assert!(sender.is_full());
+        let sender_b = sender.clone();
+        assert_eq!(receiver_b.sender_count(), 2);
+        drop((sender, sender_b));
+        // the tombstone isn't a value
+        assert_eq!(receiver_b.pending(), 7);
+
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+        for value in 0..4 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.len(), 4);
+        assert!(sender.is_full());
+        receiver.recv().expect("couldn't receive");
+        assert_eq!(receiver.pending(), 3);
+        assert!(!sender.is_full());
+
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, DeliveryMode::Lossy).expect("couldn't construct channel");
+        assert!(sender.is_empty());
+        for value in 0..6 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.len(), 4);
+        assert!(!sender.is_full());
+        assert_eq!(receiver.pending(), 4);
+        assert_eq!(Receiver::skip(&mut receiver, 3), Ok(3));
+        assert_eq!(receiver.pending(), 3);
+    }
+
+    #[test]
+    fn reserve_then_send() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        let permit = sender.reserve().expect("couldn't reserve");
+        // other senders wait for the permit
+        assert_eq!(sender.try_send(1), Err(SendError::Full(1)));
+        assert_eq!(permit.seq(), Some(0));
+        permit.send(0);
+        // dropping a permit gives the slot back without sending
+        let permit = sender.try_reserve().expect("couldn't reserve");
+        assert_eq!(permit.seq(), Some(1));
+        drop(permit);
+        assert_eq!(sender.send_with_seq(1), Ok(Some(1)));
+        assert_eq!(receiver.recv(), Ok(0));
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+
+        for value in 2..5 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert!(matches!(sender.try_reserve(), Err(SendError::Full(()))));
+        let other = sender.clone();
+        let handle = std::thread::spawn(move || {
+            let permit = other.reserve().expect("couldn't reserve");
+            permit.send(5);
+        });
+        assert_eq!(receiver.recv(), Ok(2));
+        handle.join().expect("couldn't join thread");
+        drop(receiver);
+        assert!(matches!(
+            sender.reserve(),
+            Err(SendError::Disconnected(None))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reserve_async() {
+        let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+        sender.send(1).expect("couldn't send");
+        // the future claims the write head then waits for the full buffer
+        let mut pending = Box::pin(sender.reserve_async());
+        assert!(futures_util::FutureExt::now_or_never(&mut pending).is_none());
+        drop(pending);
+        assert_eq!(receiver.recv_async().await, Ok(1));
+        let permit = sender.reserve_async().await.expect("couldn't reserve");
+        assert_eq!(permit.seq(), Some(1));
+        permit.send(2);
+        assert_eq!(receiver.recv_async().await, Ok(2));
+    }
+
+    #[test]
+    fn send_with_reuses_values() {
+        fn fill(slot: &mut Slot<'_, Vec<usize>>, value: usize) {
+            let buffer = slot.get_or_insert_with(|| Vec::with_capacity(16));
+            buffer.clear();
+            buffer.push(value);
+        }
+        for mode in [DeliveryMode::Broadcast, DeliveryMode::Lossy] {
+            let (sender, mut receiver) =
+                make_channel_with_mode(2, mode).expect("couldn't construct channel");
+            let mut allocations = Vec::new();
+            for value in 0..4 {
+                let seq = sender
+                    .send_with(|slot| fill(slot, value))
+                    .expect("couldn't send");
+                assert_eq!(seq, Some(value as u64));
+                let received = receiver.recv_ref().expect("couldn't receive");
+                assert_eq!(*received, vec![value]);
+                allocations.push(received.as_ptr());
+            }
+            assert_eq!(allocations[0], allocations[2]);
+            assert_eq!(allocations[1], allocations[3]);
+        }
+
+        // work queue receivers move values out of the channel so the slot starts empty
+        let (sender, mut receiver) =
+            make_channel_with_mode(2, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+        for value in 0..4 {
+            sender
+                .send_with(|slot| {
+                    assert!(slot.get_mut().is_none());
+                    slot.insert(vec![value]);
+                })
+                .expect("couldn't send");
+            assert_eq!(receiver.recv(), Ok(vec![value]));
+        }
+    }
+
+    #[test]
+    fn send_with_empty_slot() {
+        let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+        // nothing is sent and the id is given back
+        assert_eq!(sender.send_with(|_| {}), Ok(None));
+        assert_eq!(sender.send_with(|slot| _ = slot.insert(1)), Ok(Some(0)));
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+        drop(receiver);
+        assert!(matches!(
+            sender.send_with(|slot| _ = slot.insert(2)),
+            Err(SendError::Disconnected(None))
+        ));
+    }
+
+    #[derive(Default)]
+    struct Counters {
+        sent: Mutex<Vec<u64>>,
+        received: Mutex<Vec<u64>>,
+        lagged: AtomicUsize,
+        watermarks: Mutex<Vec<Watermark>>,
+        evicted: Mutex<Vec<u64>>,
+    }
+
+    impl Metrics for Counters {
+        fn sent(&self, seq: u64) {
+            self.sent.lock().unwrap().push(seq);
+        }
+
+        fn received(&self, seq: u64) {
+            self.received.lock().unwrap().push(seq);
+        }
+
+        fn lagged(&self, missed: usize) {
+            self.lagged.fetch_add(missed, Ordering::Relaxed);
+        }
+
+        fn watermark(&self, watermark: Watermark) {
+            self.watermarks.lock().unwrap().push(watermark);
+        }
+
+        fn evicted(&self, seq: u64) {
+            self.evicted.lock().unwrap().push(seq);
+        }
+    }
+
+    #[test]
+    fn channel_builder() {
+        assert_eq!(
+            ChannelBuilder::new(1).build::<()>().unwrap_err(),
+            NexusError::BufferTooSmall
+        );
+        assert_eq!(
+            ChannelBuilder::new(4)
+                .capacity(isize::MAX as usize + 1)
+                .build_sender::<()>()
+                .unwrap_err(),
+            NexusError::BufferTooLarge
+        );
+
+        let counters = Arc::new(Counters::default());
+        let (sender, mut receiver_a) = ChannelBuilder::new(3)
+            .name("numbers")
+            .metrics(counters.clone())
+            .build()
+            .expect("couldn't construct channel");
+        assert_eq!(sender.capacity(), 4);
+        assert_eq!(receiver_a.name(), Some("numbers"));
+        let mut receiver_b = receiver_a.join_group("group").expect("couldn't join group");
+        sender.send(1).expect("couldn't send");
+        sender
+            .send_batch([2, 3].into_iter())
+            .expect("couldn't send");
+        assert_eq!(*receiver_a.peek().expect("couldn't peek"), 1);
+        assert_eq!(receiver_a.recv(), Ok(1));
+        assert_eq!(Receiver::skip(&mut receiver_a, 1), Ok(1));
+        assert_eq!(receiver_b.recv(), Ok(1));
+        let mut received = Vec::new();
+        assert_eq!(receiver_b.try_recv_batch(2, &mut received), 2);
+        assert_eq!(*counters.sent.lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(*counters.received.lock().unwrap(), vec![0, 0, 1, 2]);
+        sender.send(4).expect("couldn't send");
+        // the skipped value isn't counted
+        assert_eq!(receiver_a.try_recv_batch(2, &mut received), 2);
+        assert_eq!(*counters.received.lock().unwrap(), vec![0, 0, 1, 2, 2, 3]);
+
+        let counters = Arc::new(Counters::default());
+        let (sender, mut receiver) = ChannelBuilder::new(2)
+            .delivery_mode(DeliveryMode::Lossy)
+            .metrics(counters.clone())
+            .build()
+            .expect("couldn't construct channel");
+        assert_eq!(sender.name(), None);
+        for value in 0..5 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(3)));
+        assert_eq!(receiver.recv(), Ok(3));
+        assert_eq!(counters.lagged.load(Ordering::Relaxed), 3);
+        assert_eq!(*counters.received.lock().unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn exact_capacity() {
+        for mode in [
+            DeliveryMode::Broadcast,
+            DeliveryMode::WorkQueue,
+            DeliveryMode::Lossy,
+        ] {
+            let (sender, mut receiver) = ChannelBuilder::new(3)
+                .exact_capacity(true)
+                .delivery_mode(mode)
+                .build()
+                .expect("couldn't construct channel");
+            assert_eq!(sender.capacity(), 3);
+            // wrap around the buffer a few times
+            for value in 0..10 {
+                sender.send(value).expect("couldn't send");
+                sender.send(value + 100).expect("couldn't send");
+                assert_eq!(receiver.recv(), Ok(value));
+                assert_eq!(receiver.recv(), Ok(value + 100));
+            }
+        }
+
+        let (sender, mut receiver) = ChannelBuilder::new(5)
+            .exact_capacity(true)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 0..4 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.try_send(4), Err(SendError::Full(4)));
+        assert!(sender.is_full());
+        assert_eq!(receiver.recv(), Ok(0));
+        sender.send(4).expect("couldn't send");
+        // the first value's cell has been reused
+        assert_eq!(receiver.seek_to_oldest(), Ok(0));
+        let mut received = Vec::new();
+        assert_eq!(receiver.try_recv_batch(10, &mut received), 4);
+        assert_eq!(received, vec![1, 2, 3, 4]);
+        assert_eq!(receiver.seek_to_oldest(), Ok(4));
+        assert_eq!(receiver.recv(), Ok(1));
+
+        let (sender, mut receiver) = ChannelBuilder::new(3)
+            .exact_capacity(true)
+            .delivery_mode(DeliveryMode::Lossy)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 0..7 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(4)));
+        assert_eq!(receiver.recv(), Ok(4));
+    }
+
+    #[test]
+    fn overflow_policy_reject() {
+        let (sender, mut receiver) = ChannelBuilder::new(4)
+            .overflow_policy(OverflowPolicy::Reject)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 1..=3 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.send(4), Err(SendError::Full(4)));
+        assert_eq!(sender.try_send(4), Err(SendError::Full(4)));
+        assert_eq!(
+            sender.send_timeout(4, std::time::Duration::from_secs(1)),
+            Err(SendError::Full(4))
+        );
+        assert_eq!(sender.send_batch(4..=5), Err(SendError::Full(4)));
+        assert_eq!(
+            sender.send_slice(&[4, 5]),
+            Err(SendError::Full(&[4, 5][..]))
+        );
+        assert!(matches!(sender.reserve(), Err(SendError::Full(()))));
+        assert!(matches!(sender.try_reserve(), Err(SendError::Full(()))));
+        assert_eq!(sender.dropped_count(), 0);
+
+        assert_eq!(receiver.recv(), Ok(1));
+        sender.send(4).expect("couldn't send");
+        for value in 2..=4 {
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn overflow_policy_drop_newest() {
+        let (sender, mut receiver) = ChannelBuilder::new(4)
+            .overflow_policy(OverflowPolicy::DropNewest)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 1..=3 {
+            sender.send(value).expect("couldn't send");
+        }
+        sender.send(4).expect("couldn't send");
+        assert_eq!(sender.send_with_seq(4), Ok(None));
+        sender.try_send(4).expect("couldn't send");
+        sender
+            .send_timeout(4, std::time::Duration::from_secs(1))
+            .expect("couldn't send");
+        // dropped values aren't counted as sent
+        assert_eq!(sender.send_batch(4..=5), Ok(0));
+        let permit = sender.reserve().expect("couldn't reserve");
+        assert_eq!(permit.seq(), None);
+        permit.send(4);
+        assert_eq!(
+            sender
+                .try_reserve()
+                .expect("couldn't reserve")
+                .send_with(|slot| {
+                    slot.insert(4);
+                }),
+            None
+        );
+        assert_eq!(sender.dropped_count(), 8);
+
+        for value in 1..=3 {
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+        assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+        sender.send(4).expect("couldn't send");
+        assert_eq!(receiver.recv(), Ok(4));
+        assert_eq!(sender.dropped_count(), 8);
+    }
+
+    #[test]
+    fn overflow_policy_drop_oldest() {
+        let (sender, mut receiver) = ChannelBuilder::new(4)
+            .delivery_mode(DeliveryMode::WorkQueue)
+            .overflow_policy(OverflowPolicy::DropOldest)
+            .build()
+            .expect("couldn't construct channel");
+        let mut receiver_b = receiver.clone();
+        for value in 1..=4 {
+            sender.send(value).expect("couldn't send");
+        }
+        sender.send(5).expect("couldn't send");
+        sender.try_send(6).expect("couldn't send");
+        assert_eq!(sender.send_batch(7..=8), Ok(2));
+        sender.reserve().expect("couldn't reserve").send(9);
+        assert_eq!(sender.dropped_count(), 5);
+        for value in 6..=9 {
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+
+        // a value that is being received isn't evicted
+        for value in 10..=13 {
+            sender.send(value).expect("couldn't send");
+        }
+        let value = receiver.recv_ref().expect("couldn't receive");
+        assert_eq!(*value, 10);
+        assert_eq!(sender.try_send(14), Err(SendError::Full(14)));
+        drop(value);
+        sender.send(14).expect("couldn't send");
+        sender.send(15).expect("couldn't send");
+        assert_eq!(sender.dropped_count(), 6);
+        assert_eq!(receiver_b.recv(), Ok(12));
+        for value in 13..=15 {
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn overflow_policy_async() {
+        let (mut sender, mut receiver) = ChannelBuilder::new(2)
+            .overflow_policy(OverflowPolicy::Reject)
+            .build()
+            .expect("couldn't construct channel");
+        SinkExt::send(&mut sender, 1).await.expect("couldn't send");
+        assert_eq!(SinkExt::send(&mut sender, 2).await, Err(SendError::Full(2)));
+        assert_eq!(sender.send_async(2).await, Err(SendError::Full(2)));
+        assert!(matches!(
+            sender.reserve_async().await,
+            Err(SendError::Full(()))
+        ));
+        assert_eq!(receiver.next().await, Some(1));
+        SinkExt::send(&mut sender, 2).await.expect("couldn't send");
+        assert_eq!(receiver.next().await, Some(2));
+
+        let (mut sender, mut receiver) = ChannelBuilder::new(2)
+            .overflow_policy(OverflowPolicy::DropNewest)
+            .build()
+            .expect("couldn't construct channel");
+        SinkExt::send(&mut sender, 1).await.expect("couldn't send");
+        SinkExt::send(&mut sender, 2).await.expect("couldn't send");
+        sender.send_async(2).await.expect("couldn't send");
+        sender
+            .reserve_async()
+            .await
+            .expect("couldn't reserve")
+            .send(2);
+        assert_eq!(sender.dropped_count(), 3);
+        assert_eq!(receiver.next().await, Some(1));
+        SinkExt::send(&mut sender, 2).await.expect("couldn't send");
+        assert_eq!(receiver.next().await, Some(2));
+
+        let (mut sender, mut receiver) = ChannelBuilder::new(2)
+            .delivery_mode(DeliveryMode::WorkQueue)
+            .overflow_policy(OverflowPolicy::DropOldest)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 1..=4 {
+            SinkExt::send(&mut sender, value)
+                .await
+                .expect("couldn't send");
+        }
+        sender.send_async(5).await.expect("couldn't send");
+        assert_eq!(sender.dropped_count(), 3);
+        assert_eq!(receiver.next().await, Some(4));
+        assert_eq!(receiver.next().await, Some(5));
+
+        let (mut sender, mut receiver) = ChannelBuilder::new(2)
+            .overflow_policy(OverflowPolicy::DropOldest)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 1..=3 {
+            SinkExt::send(&mut sender, value)
+                .await
+                .expect("couldn't send");
+        }
+        assert_eq!(sender.dropped_count(), 2);
+        assert_eq!(receiver.try_recv(), Err(RecvError::Lagged(2)));
+        assert_eq!(receiver.next().await, Some(3));
+    }
+
+    #[test]
+    fn overflow_policy_drop_oldest_broadcast() {
+        let counters = Arc::new(Counters::default());
+        let (sender, mut receiver) = ChannelBuilder::new(4)
+            .overflow_policy(OverflowPolicy::DropOldest)
+            .metrics(counters.clone())
+            .build()
+            .expect("couldn't construct channel");
+        let mut group = receiver.join_group("group").expect("couldn't join group");
+        let mut receiver_b = receiver.clone();
+        // receivers hold the cell before their cursor so they lose a value before the group does
+        for value in 0..5 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.dropped_count(), 2);
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(2)));
+        for value in 2..5 {
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+        assert_eq!(receiver_b.try_recv(), Err(RecvError::Lagged(2)));
+        assert_eq!(receiver_b.try_recv(), Ok(2));
+        assert_eq!(counters.lagged.load(Ordering::Relaxed), 4);
+        for value in 1..5 {
+            assert_eq!(group.recv(), Ok(value));
+        }
+
+        // a receiver that is reading a value isn't moved on
+        let (sender, mut receiver) = ChannelBuilder::new(4)
+            .overflow_policy(OverflowPolicy::DropOldest)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 0..4 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(receiver.try_recv(), Err(RecvError::Lagged(1)));
+        let value = receiver.recv_ref().expect("couldn't receive");
+        assert_eq!(*value, 1);
+        sender.send(4).expect("couldn't send");
+        assert_eq!(sender.try_send(5), Err(SendError::Full(5)));
+        drop(value);
+        sender.send(5).expect("couldn't send");
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(1)));
+        for value in 3..6 {
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+        assert_eq!(sender.dropped_count(), 2);
+    }
+
+    #[test]
+    fn lossy_channels_are_never_full() {
+        let (sender, mut receiver) = ChannelBuilder::new(2)
+            .delivery_mode(DeliveryMode::Lossy)
+            .overflow_policy(OverflowPolicy::Reject)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 0..4 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.dropped_count(), 0);
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(2)));
+    }
+
+    #[test]
+    fn watermarks() {
+        for (low, high) in [(2, 2), (3, 2), (1, 9)] {
+            assert_eq!(
+                ChannelBuilder::new(8)
+                    .watermarks(low, high)
+                    .build::<usize>()
+                    .unwrap_err(),
+                NexusError::InvalidWatermarks
+            );
+        }
+
+        for mode in [DeliveryMode::Broadcast, DeliveryMode::WorkQueue] {
+            let counters = Arc::new(Counters::default());
+            let (sender, mut receiver) = ChannelBuilder::new(8)
+                .delivery_mode(mode)
+                .watermarks(2, 5)
+                .metrics(counters.clone())
+                .build()
+                .expect("couldn't construct channel");
+            assert_eq!(sender.watermark(), Watermark::Low);
+            // waiting for the current watermark returns straight away
+            sender.wait_for_watermark(Watermark::Low);
+            for value in 0..4 {
+                sender.send(value).expect("couldn't send");
+            }
+            assert_eq!(sender.watermark(), Watermark::Low);
+            sender.send(4).expect("couldn't send");
+            assert_eq!(sender.watermark(), Watermark::High);
+            sender.wait_for_watermark(Watermark::High);
+
+            // the watermark stays high until the occupancy falls to the low watermark
+            assert_eq!(receiver.recv(), Ok(0));
+            assert_eq!(receiver.recv(), Ok(1));
+            assert_eq!(sender.watermark(), Watermark::High);
+            assert_eq!(receiver.recv(), Ok(2));
+            assert_eq!(sender.watermark(), Watermark::Low);
+            let mut received = Vec::new();
+            assert_eq!(receiver.try_recv_batch(2, &mut received), 2);
+            sender.send_batch(5..10).expect("couldn't send");
+            assert_eq!(sender.watermark(), Watermark::High);
+            assert_eq!(
+                *counters.watermarks.lock().unwrap(),
+                vec![Watermark::High, Watermark::Low, Watermark::High]
+            );
+
+            // receivers that go away no longer hold the channel back
+            drop(receiver);
+            assert_eq!(sender.watermark(), Watermark::Low);
+        }
+
+        // the slowest broadcast receiver sets the occupancy
+        let (sender, mut receiver) = ChannelBuilder::new(8)
+            .watermarks(0, 3)
+            .build()
+            .expect("couldn't construct channel");
+        let mut receiver_b = sender.subscribe();
+        for value in 0..3 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.watermark(), Watermark::High);
+        let handle = std::thread::spawn(move || {
+            for value in 0..3 {
+                assert_eq!(receiver.recv(), Ok(value));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            for value in 0..3 {
+                assert_eq!(receiver_b.recv(), Ok(value));
+            }
+        });
+        sender.wait_for_watermark(Watermark::Low);
+        assert_eq!(sender.len(), 0);
+        handle.join().expect("couldn't join receivers");
+
+        // seeking moves the occupancy without receiving anything
+        let (sender, mut receiver) = ChannelBuilder::new(8)
+            .watermarks(0, 3)
+            .build()
+            .expect("couldn't construct channel");
+        for value in 0..3 {
+            sender.send(value).expect("couldn't send");
+        }
+        assert_eq!(sender.watermark(), Watermark::High);
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            assert_eq!(receiver.seek_to_latest(), Ok(3));
+            receiver
+        });
+        sender.wait_for_watermark(Watermark::Low);
+        let mut receiver = handle.join().expect("couldn't join receiver");
+        assert_eq!(receiver.seek_to_oldest(), Ok(3));
+        assert_eq!(sender.watermark(), Watermark::High);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn watermarks_async() {
+        let (sender, mut receiver) = ChannelBuilder::new(8)
+            .delivery_mode(DeliveryMode::WorkQueue)
+            .watermarks(1, 3)
+            .build()
+            .expect("couldn't construct channel");
+        sender.wait_for_watermark_async(Watermark::Low).await;
+        let handle = tokio::spawn(async move {
+            for value in 0..6 {
+                assert_eq!(receiver.recv_async().await, Ok(value));
+            }
+        });
+        for value in 0..6 {
+            if sender.watermark() == Watermark::High {
+                sender.wait_for_watermark_async(Watermark::Low).await;
+            }
+            sender.send_async(value).await.expect("couldn't send");
+        }
+        handle.await.expect("couldn't join receiver");
+        sender.wait_for_watermark_async(Watermark::Low).await;
+        assert!(sender.is_empty());
+    }
+
+    #[test]
+    fn stall_policy_validation() {
+        for mode in [DeliveryMode::WorkQueue, DeliveryMode::Lossy] {
+            assert_eq!(
+                ChannelBuilder::new(8)
+                    .delivery_mode(mode)
+                    .stall_policy(StallPolicy::MaxLag(2))
+                    .build::<usize>()
+                    .unwrap_err(),
+                NexusError::StallPolicyRequiresBroadcast
+            );
+        }
+        for max_lag in [0, 7] {
+            assert_eq!(
+                ChannelBuilder::new(8)
+                    .stall_policy(StallPolicy::MaxLag(max_lag))
+                    .build::<usize>()
+                    .unwrap_err(),
+                NexusError::InvalidMaxLag
+            );
+        }
+        assert!(ChannelBuilder::new(8)
+            .stall_policy(StallPolicy::MaxLag(6))
+            .build::<usize>()
+            .is_ok());
+    }
+
+    #[test]
+    fn stall_policy_timeout() {
+        let timeout = std::time::Duration::from_millis(20);
+        let counters = Arc::new(Counters::default());
+        let (sender, mut receiver) = ChannelBuilder::new(4)
+            .stall_policy(StallPolicy::Timeout(timeout))
+            .metrics(counters.clone())
+            .build()
+            .expect("couldn't construct channel");
+        let mut stalled = receiver.clone();
+        for value in 0..3 {
+            sender.send(value).expect("couldn't send");
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+        // the stalled receiver holds the cell the next value goes in
+        let start = std::time::Instant::now();
+        sender.send(3).expect("couldn't send");
+        assert!(start.elapsed() >= timeout);
+        assert_eq!(sender.receiver_count(), 1);
+        assert!(stalled.is_evicted());
+        assert_eq!(stalled.recv(), Err(RecvError::Evicted));
+        assert_eq!(stalled.try_recv(), Err(RecvError::Evicted));
+        assert_eq!(Receiver::skip(&mut stalled, 1), Err(RecvError::Evicted));
+        let mut clone = stalled.clone();
+        assert_eq!(clone.recv(), Err(RecvError::Evicted));
+        drop(stalled);
+        drop(clone);
+        assert_eq!(sender.receiver_count(), 1);
+        assert_eq!(receiver.recv(), Ok(3));
+
+        // a receiver borrowing a value isn't evicted until it's done with it
+        let mut borrower = receiver.clone();
+        for value in 4..7 {
+            sender.send(value).expect("couldn't send");
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+        let value = borrower.recv_ref().expect("couldn't receive");
+        sender.send(7).expect("couldn't send");
+        assert_eq!(
+            sender.send_timeout(8, timeout * 3),
+            Err(SendError::Timeout(8))
+        );
+        assert_eq!(*value, 4);
+        drop(value);
+        assert!(!borrower.is_evicted());
+        sender.send(8).expect("couldn't send");
+        assert!(borrower.is_evicted());
+        assert_eq!(receiver.recv(), Ok(7));
+        assert_eq!(receiver.recv(), Ok(8));
+        assert_eq!(*counters.evicted.lock().unwrap(), vec![0, 5]);
+    }
+
+    #[test]
+    fn stall_policy_max_lag() {
+        let (sender, mut receiver) = ChannelBuilder::new(8)
+            .stall_policy(StallPolicy::MaxLag(3))
+            .build()
+            .expect("couldn't construct channel");
+        let mut slow = receiver.clone();
+        for value in 0..3 {
+            sender.send(value).expect("couldn't send");
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+        assert_eq!(slow.recv(), Ok(0));
+        sender.send(3).expect("couldn't send");
+        assert!(!slow.is_evicted());
+        // the slow receiver is now four values behind
+        sender.send(4).expect("couldn't send");
+        assert!(slow.is_evicted());
+        assert_eq!(slow.try_recv(), Err(RecvError::Evicted));
+        assert!(matches!(slow.peek(), Err(RecvError::Evicted)));
+        assert_eq!(slow.try_recv_batch(4, &mut Vec::new()), 0);
+        assert_eq!(sender.receiver_count(), 1);
+        for value in 3..5 {
+            assert_eq!(receiver.recv(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn evicting_every_receiver_disconnects() {
+        let (sender, mut receiver) = ChannelBuilder::new(4)
+            .stall_policy(StallPolicy::MaxLag(1))
+            .build()
+            .expect("couldn't construct channel");
+        sender.send(0).expect("couldn't send");
+        sender.send(1).expect("couldn't send");
+        assert!(receiver.is_evicted());
+        // the evicted receiver still exists but no longer counts as a receiver
+        assert_eq!(sender.receiver_count(), 0);
+        assert_eq!(sender.send(2), Err(SendError::Disconnected(Some(2))));
+        assert_eq!(receiver.recv(), Err(RecvError::Evicted));
+        let mut late = sender.subscribe();
+        sender.send(3).expect("couldn't send");
+        assert_eq!(late.recv(), Ok(3));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn stall_policy_async() {
+        let (sender, mut receiver) = ChannelBuilder::new(2)
+            .stall_policy(StallPolicy::Timeout(std::time::Duration::from_millis(20)))
+            .build()
+            .expect("couldn't construct channel");
+        let mut stalled = receiver.clone();
+        sender.send_async(1).await.expect("couldn't send");
+        assert_eq!(receiver.recv_async().await, Ok(1));
+        sender.send_async(2).await.expect("couldn't send");
+        assert_eq!(receiver.recv_async().await, Ok(2));
+        assert_eq!(stalled.recv_async().await, Err(RecvError::Evicted));
+        assert_eq!(stalled.next().await, None);
+    }
+
+    #[test]
+    fn buffer_min_size() {
+        assert_eq!(
+            make_channel::<()>(0).unwrap_err(),
+            NexusError::BufferTooSmall
+        );
+        assert_eq!(
+            make_channel::<()>(1).unwrap_err(),
+            NexusError::BufferTooSmall
+        );
+        assert!(make_channel::<()>(2).is_ok());
+    }
+
+    #[test]
+    fn buffer_too_big() {
+        assert_eq!(
+            make_channel::<()>(isize::MAX as usize + 1).unwrap_err(),
+            NexusError::BufferTooLarge
+        );
+        //Don't actually test a valid but extremely large buffer as that would kill ram for tests...
+    }
+}
+
+#[cfg(test)]
+mod drop_tests {
+    use super::*;
+    use portable_atomic::{AtomicU64, Ordering};
+    use pretty_assertions_sorted::assert_eq;
 
     #[derive(Debug, Clone)]
     struct CustomDropper {
@@ -333,7 +3079,7 @@ mod drop_tests {
                 .send(CustomDropper::new(&counter))
                 .expect("couldn't send");
         }
-        receiver.recv();
+        receiver.recv().expect("couldn't receive");
         sender
             .send(CustomDropper::new(&counter))
             .expect("couldn't send");
@@ -376,9 +3122,9 @@ mod drop_tests {
         sender
             .send(CustomDropper::new(&counter))
             .expect("couldn't send");
-        receiver.recv();
-        receiver.recv();
-        receiver.recv();
+        receiver.recv().expect("couldn't receive");
+        receiver.recv().expect("couldn't receive");
+        receiver.recv().expect("couldn't receive");
         assert_eq!(counter.load(Ordering::Acquire), 3);
         sender
             .send(CustomDropper::new(&counter))
@@ -419,4 +3165,31 @@ mod fast_mod_tests {
             }
         }
     }
+
+    #[test]
+    fn test_fast_mod_by() {
+        let denominators = [
+            1,
+            2,
+            3,
+            5,
+            7,
+            100,
+            1000,
+            1023,
+            1024,
+            1025,
+            (1 << 30) + 1,
+            u64::MAX,
+        ];
+        for d in denominators {
+            let divisor = Divisor::new(d);
+            let numerators = (0..2048).chain([u64::MAX - 1, u64::MAX, d - 1, d, d.wrapping_add(1)]);
+            for n in numerators {
+                assert_eq!(n.fast_mod_by(&divisor), n % d);
+            }
+        }
+        let divisor = Divisor::new(1000);
+        assert_eq!(123_456_usize.fast_mod_by(&divisor), 456);
+    }
 }
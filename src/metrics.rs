@@ -0,0 +1,61 @@
+use crate::Watermark;
+
+/// Hooks that are called as values move through a channel. Set using
+/// [`ChannelBuilder::metrics`](crate::ChannelBuilder::metrics).
+///
+/// Hooks are called on the sending and receiving threads while values are being sent and received
+/// so they should be cheap, for example incrementing counters. Every hook does nothing by default.
+///
+/// # Examples
+/// ```rust
+/// use nexusq2::{ChannelBuilder, Metrics};
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct Counters {
+///     sent: AtomicU64,
+///     received: AtomicU64,
+/// }
+///
+/// impl Metrics for Counters {
+///     fn sent(&self, _seq: u64) {
+///         self.sent.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn received(&self, _seq: u64) {
+///         self.received.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counters = Arc::new(Counters::default());
+/// let (sender, mut receiver) = ChannelBuilder::new(4)
+///     .metrics(counters.clone())
+///     .build()
+///     .expect("couldn't construct channel");
+/// sender.send(1).expect("couldn't send");
+/// assert_eq!(receiver.recv(), Ok(1));
+/// assert_eq!(counters.sent.load(Ordering::Relaxed), 1);
+/// assert_eq!(counters.received.load(Ordering::Relaxed), 1);
+/// ```
+pub trait Metrics: Send + Sync {
+    /// Called once a value has been sent with the sequence number it was given
+    fn sent(&self, _seq: u64) {}
+
+    /// Called when a receiver receives a value with the value's sequence number. Peeking at a value
+    /// or skipping over it doesn't count as receiving it.
+    fn received(&self, _seq: u64) {}
+
+    /// Called when a receiver in a [`DeliveryMode::Lossy`](crate::DeliveryMode::Lossy) channel
+    /// falls behind with the number of values it missed
+    fn lagged(&self, _missed: usize) {}
+
+    /// Called when the channel's occupancy crosses one of the watermarks set by
+    /// [`ChannelBuilder::watermarks`](crate::ChannelBuilder::watermarks)
+    fn watermark(&self, _watermark: Watermark) {}
+
+    /// Called when a receiver is evicted by the channel's
+    /// [`StallPolicy`](crate::StallPolicy) with the sequence number of the next value it would
+    /// have received
+    fn evicted(&self, _seq: u64) {}
+}
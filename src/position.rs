@@ -0,0 +1,104 @@
+use portable_atomic::{AtomicUsize, Ordering};
+
+// The receiver isn't using the channel and can be evicted or moved on
+const IDLE: usize = 0;
+// The receiver is reading from the channel or borrowing a value from it
+const ACTIVE: usize = 1;
+// A sender is checking whether the receiver has stalled or is moving it on
+const CHECKING: usize = 2;
+// The receiver was evicted and no longer holds its place in the channel
+const EVICTED: usize = 3;
+
+/// The position of a broadcast receiver that holds its place in the buffer. Senders use the
+/// positions to find the oldest unread value, to evict receivers that stall the channel and to
+/// move receivers on when the oldest value is dropped to make room.
+#[derive(Debug)]
+pub struct Position {
+    pub cursor: AtomicUsize,
+    state: AtomicUsize,
+}
+
+impl Position {
+    pub const fn new(cursor: usize) -> Self {
+        Self {
+            cursor: AtomicUsize::new(cursor),
+            state: AtomicUsize::new(IDLE),
+        }
+    }
+
+    /// Create the position of a receiver that has already been evicted
+    pub const fn evicted(cursor: usize) -> Self {
+        Self {
+            cursor: AtomicUsize::new(cursor),
+            state: AtomicUsize::new(EVICTED),
+        }
+    }
+
+    /// Stop the receiver from being evicted while it uses the channel. Returns false if it has
+    /// already been evicted. Must only be called by the receiver while it's idle.
+    pub fn activate(&self) -> bool {
+        loop {
+            match self.state.compare_exchange_weak(
+                IDLE,
+                ACTIVE,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(EVICTED) => return false,
+                // a sender is checking the cursor which doesn't take long
+                Err(_) => core::hint::spin_loop(),
+            }
+        }
+    }
+
+    pub fn deactivate(&self) {
+        self.state.store(IDLE, Ordering::Release);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state.load(Ordering::Acquire) == ACTIVE
+    }
+
+    pub fn is_evicted(&self) -> bool {
+        self.state.load(Ordering::Acquire) == EVICTED
+    }
+
+    /// Evict the receiver if it's idle and its cursor is before `min_cursor`. Returns the cursor
+    /// it was evicted at. The caller takes over the receiver's hold on the cell before it.
+    pub fn evict_before(&self, min_cursor: usize) -> Option<usize> {
+        self.state
+            .compare_exchange(IDLE, CHECKING, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        // the receiver can't move while it's being checked
+        let cursor = self.cursor.load(Ordering::Acquire);
+        if cursor < min_cursor {
+            self.state.store(EVICTED, Ordering::Release);
+            Some(cursor)
+        } else {
+            self.state.store(IDLE, Ordering::Release);
+            None
+        }
+    }
+
+    /// Move the receiver on to the next id if it's idle and its cursor is at `cursor`. `move_hold`
+    /// moves the receiver's hold to the next cell before the receiver can use it again. Returns
+    /// true if the receiver was moved.
+    pub fn move_on_from(&self, cursor: usize, move_hold: impl FnOnce()) -> bool {
+        if self
+            .state
+            .compare_exchange(IDLE, CHECKING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        // the receiver can't move while it's being checked
+        let moved = self.cursor.load(Ordering::Acquire) == cursor;
+        if moved {
+            move_hold();
+            self.cursor.store(cursor.wrapping_add(1), Ordering::Release);
+        }
+        self.state.store(IDLE, Ordering::Release);
+        moved
+    }
+}
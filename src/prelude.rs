@@ -3,6 +3,9 @@ pub trait FastMod {
     fn fast_mod(&self, denominator: Self) -> Self;
     #[must_use]
     fn maybe_next_power_of_two(&self) -> Self;
+    /// Like [`FastMod::fast_mod`] but the denominator doesn't have to be a power of two
+    #[must_use]
+    fn fast_mod_by(&self, divisor: &Divisor) -> Self;
 }
 
 impl FastMod for usize {
@@ -15,6 +18,10 @@ impl FastMod for usize {
     fn maybe_next_power_of_two(&self) -> Self {
         self.next_power_of_two()
     }
+
+    fn fast_mod_by(&self, divisor: &Divisor) -> Self {
+        divisor.modulo(*self as u64) as Self
+    }
 }
 
 impl FastMod for u64 {
@@ -27,4 +34,43 @@ impl FastMod for u64 {
     fn maybe_next_power_of_two(&self) -> Self {
         self.next_power_of_two()
     }
+
+    fn fast_mod_by(&self, divisor: &Divisor) -> Self {
+        divisor.modulo(*self)
+    }
+}
+
+/// A denominator with precomputed constants so that taking the modulo doesn't need a division.
+/// Powers of two use a mask. Other denominators use Lemire's fastmod which replaces the division
+/// with a couple of multiplications.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Divisor {
+    denominator: u64,
+    // ceil(2^128 / denominator) or 0 if the denominator is a power of two
+    magic: u128,
+}
+
+impl Divisor {
+    pub const fn new(denominator: u64) -> Self {
+        assert!(denominator > 0);
+        let magic = if denominator.is_power_of_two() {
+            0
+        } else {
+            u128::MAX / denominator as u128 + 1
+        };
+        Self { denominator, magic }
+    }
+
+    pub const fn modulo(&self, numerator: u64) -> u64 {
+        if self.magic == 0 {
+            return numerator & (self.denominator - 1);
+        }
+        // the fractional part of numerator / denominator scaled up to 128 bits
+        let fraction = self.magic.wrapping_mul(numerator as u128);
+        // the top 64 bits of fraction * denominator
+        let denominator = self.denominator as u128;
+        let low = ((fraction & u64::MAX as u128) * denominator) >> 64;
+        let high = (fraction >> 64) * denominator;
+        ((low + high) >> 64) as u64
+    }
 }
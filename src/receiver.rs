@@ -1,15 +1,21 @@
-use crate::prelude::FastMod;
+use crate::group::Group;
+use crate::position::Position;
+use crate::timer::Deadline;
 use crate::wait_strategy::AsyncEventGuard;
-use crate::{cell::Cell, NexusQ};
+use crate::{cell::Cell, sequence_number, DeliveryMode, NexusError, NexusQ};
 use alloc::sync::Arc;
 use core::fmt::{Debug, Formatter};
+use portable_atomic::AtomicUsize;
 use std::pin::Pin;
 use std::sync::atomic::Ordering;
 use std::task::{Context, Poll};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error as ThisError;
 
 /// An error that can occur when receiving data from a `NexusQ`.
+///
+/// Every receive can fail with [`RecvError::Disconnected`], [`RecvError::Lagged`] or
+/// [`RecvError::Evicted`]. The other errors only come from the methods that document them.
 #[derive(Debug, ThisError, PartialOrd, PartialEq, Ord, Eq, Clone, Copy)]
 pub enum RecvError {
     /// The operation timed out.
@@ -18,16 +24,81 @@ pub enum RecvError {
     /// There is no unread data to be received
     #[error("there's no new data available to be read")]
     NoNewData,
+    /// Every sender has been dropped or the channel was closed, and every value has been read.
+    /// Continued use will always return this error.
+    #[error("there are no more senders and no more data. The channel is disconnected")]
+    Disconnected,
+    /// The receiver fell behind and missed the given number of values. The next read continues from
+    /// the oldest value still in the channel. Only returned in [`DeliveryMode::Lossy`] mode or with
+    /// [`OverflowPolicy::DropOldest`](crate::OverflowPolicy::DropOldest).
+    #[error("receiver lagged behind and missed {0} values")]
+    Lagged(usize),
+    /// The operation isn't supported by receivers that share their position with other receivers.
+    /// Receivers in [`DeliveryMode::WorkQueue`] mode and consumer group members share their position.
+    #[error("the operation isn't supported by receivers that share their position")]
+    Unsupported,
+    /// The receiver held up the channel and was evicted by the channel's [`StallPolicy`]. It no
+    /// longer holds its place in the channel. Continued use will always return this error.
+    #[error("the receiver was evicted for holding up the channel")]
+    Evicted,
+}
+
+/// When a broadcast receiver that holds up the channel is evicted. Set using
+/// [`ChannelBuilder::stall_policy`](crate::ChannelBuilder::stall_policy).
+///
+/// An evicted receiver releases its place in the channel so that senders can carry on. Every
+/// operation on it then fails with [`RecvError::Evicted`]. A receiver can only be evicted between
+/// operations, so a receiver that is waiting for a value or borrowing one using
+/// [`Receiver::recv_ref`] or [`Receiver::peek`] is never evicted. Receivers in
+/// [`DeliveryMode::WorkQueue`] mode or in a consumer group don't hold their own place in the
+/// channel and are never evicted.
+///
+/// Evicted receivers are no longer counted by [`Sender::receiver_count`](crate::Sender::receiver_count).
+/// Once every receiver has been evicted the channel has no receivers and sends follow the
+/// channel's [`NoReceiverPolicy`](crate::NoReceiverPolicy), even though the evicted receivers
+/// haven't been dropped.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum StallPolicy {
+    /// Receivers are never evicted and senders wait for them for as long as it takes
+    #[default]
+    Never,
+    /// Evict the receivers that a sender has been waiting on for longer than the timeout
+    Timeout(Duration),
+    /// Evict a receiver once it falls more than the given number of values behind the latest value
+    /// sent
+    MaxLag(usize),
+}
+
+/// Stops a broadcast receiver from being evicted until it's dropped. See [`Receiver::activate`].
+#[derive(Debug)]
+struct Active(Option<Arc<Position>>);
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        if let Some(position) = &self.0 {
+            position.deactivate();
+        }
+    }
 }
 
 /// A receiver handle for a `NexusQ`.
 /// This handle can be cloned and sent to other threads.
-/// Once all receivers have gone out of scope the `NexusQ` will be closed and is not recoverable.
-/// Send handles can be safely made from receiver handles.
+/// Send handles can be safely made from receiver handles and new receivers can subscribe from a
+/// sender using [`Sender::subscribe`](crate::Sender::subscribe).
+/// Once all senders have gone out of scope the receiver will read any remaining values before
+/// returning [`RecvError::Disconnected`].
+///
+/// In [`DeliveryMode::WorkQueue`] mode receivers compete for values and each value is received by
+/// exactly one receiver. Receivers in a consumer group (see [`Receiver::join_group`]) compete with
+/// the other members of their group.
 pub struct Receiver<T> {
     nexus: Arc<NexusQ<T>>,
     buffer: Arc<[Cell<T>]>,
+    // The consumer group this receiver is a member of
+    group: Option<Arc<Group>>,
     cursor: usize,
+    // The cursor shared with the other receivers. Only tracked for broadcast receivers that hold their cell
+    position: Option<Arc<Position>>,
     previous_cell_index: usize,
     // this is only used for async!
     current_event: Option<Pin<Box<dyn AsyncEventGuard>>>,
@@ -42,7 +113,9 @@ where
         f.debug_struct("Receiver")
             .field("nexus", &self.nexus)
             .field("buffer", &self.buffer)
+            .field("group", &self.group)
             .field("cursor", &self.cursor)
+            .field("position", &self.position)
             .field("previous_cell", &self.previous_cell_index)
             .field(
                 "current_event",
@@ -59,42 +132,1008 @@ where
 #[allow(clippy::non_send_fields_in_send_ty)]
 unsafe impl<T> Send for Receiver<T> {}
 
+/// How a [`RecvRef`] stops the value it borrows from being overwritten
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hold {
+    /// Broadcast receivers hold the cell at their cursor until they move on
+    Cursor,
+    /// The value was claimed from a shared read head and is released when the borrow is dropped
+    Claim,
+    /// Lossy receivers hold the cell only while the borrow is alive
+    Pin,
+    /// Broadcast receivers that peek at a value stop it from being overwritten by holding the cell
+    /// before it
+    Peek,
+}
+
+/// A borrow of a value received using [`Receiver::recv_ref`] or peeked at using [`Receiver::peek`].
+/// The value can't be overwritten until the borrow is dropped.
+///
+/// In [`DeliveryMode::Lossy`] mode senders writing to the cell wait for the borrow to be dropped so
+/// it shouldn't be held for long.
+pub struct RecvRef<'a, T> {
+    nexus: &'a NexusQ<T>,
+    cell: &'a Cell<T>,
+    hold: Hold,
+    // The position of the receiver that holds the borrow if it's tracked
+    position: Option<&'a Position>,
+    // Stops the receiver that holds the borrow from being evicted
+    active: Active,
+}
+
+impl<'a, T> RecvRef<'a, T> {
+    /// Take the value if no other receiver will read it, otherwise keep borrowing it
+    fn into_received(self) -> Received<'a, T> {
+        let id = self.cell.get_published();
+        let is_released = match self.hold {
+            Hold::Cursor => self.cell.is_released(),
+            Hold::Claim => self.cell.is_last_claim(),
+            Hold::Pin | Hold::Peek => false,
+        };
+        if !is_released {
+            return Received::Borrowed(self);
+        }
+        let positions = self.nexus.lock_positions();
+        if !NexusQ::<T>::others_finished(&positions, self.position, id) {
+            drop(positions);
+            return Received::Borrowed(self);
+        }
+        let value = unsafe { self.cell.move_out() };
+        drop(positions);
+        // release the claim on the now empty cell
+        drop(self);
+        Received::Owned(value)
+    }
+}
+
+/// A value received using [`Receiver::recv_take`]
+#[derive(Debug)]
+pub enum Received<'a, T> {
+    /// Other receivers may still read the value so it's borrowed
+    Borrowed(RecvRef<'a, T>),
+    /// This receiver was the last to read the value and has taken it out of the channel
+    Owned(T),
+}
+
+impl<T> core::ops::Deref for Received<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(value) => value,
+            Self::Owned(value) => value,
+        }
+    }
+}
+
+impl<T> RecvRef<'_, T> {
+    /// The sequence number of the borrowed value. See [`Receiver::recv_with_seq`].
+    #[must_use]
+    pub fn seq(&self) -> u64 {
+        sequence_number(self.cell.get_published())
+    }
+}
+
+impl<T> core::ops::Deref for RecvRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.cell.get() }
+    }
+}
+
+impl<T> Debug for RecvRef<'_, T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("RecvRef").field(&**self).finish()
+    }
+}
+
+impl<T> Drop for RecvRef<'_, T> {
+    fn drop(&mut self) {
+        match self.hold {
+            // nothing is held by the borrow itself
+            Hold::Cursor | Hold::Peek => {}
+            Hold::Claim => {
+                self.cell.release();
+                // The tombstone may be waiting on this cell to be released
+                self.nexus.publish_tombstone();
+            }
+            Hold::Pin => self.cell.move_from(),
+        }
+    }
+}
+
+/// A future that receives the next value from the channel. Created by [`Receiver::recv_async`] and
+/// its deadline variants. The future doesn't depend on an async runtime.
+#[derive(Debug)]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a mut Receiver<T>,
+    deadline: Option<Deadline>,
+}
+
+impl<T> core::future::Future for RecvFuture<'_, T>
+where
+    T: Clone,
+{
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = Pin::get_mut(self);
+        match futures_util::Stream::poll_next(Pin::new(&mut *this.receiver), cx) {
+            Poll::Ready(Some(value)) => Poll::Ready(Ok(value)),
+            Poll::Ready(None) if this.receiver.is_evicted() => Poll::Ready(Err(RecvError::Evicted)),
+            Poll::Ready(None) => Poll::Ready(Err(RecvError::Disconnected)),
+            Poll::Pending => {
+                if !this
+                    .deadline
+                    .as_mut()
+                    .is_some_and(|deadline| deadline.poll_elapsed(cx))
+                {
+                    return Poll::Pending;
+                }
+                this.receiver.current_event = None;
+                Poll::Ready(Err(RecvError::Timeout))
+            }
+        }
+    }
+}
+
+/// A future that borrows the next value without receiving it. Created by [`Receiver::peek_async`].
+#[derive(Debug)]
+pub struct PeekFuture<'a, T> {
+    receiver: Option<&'a mut Receiver<T>>,
+}
+
+impl<'a, T> core::future::Future for PeekFuture<'a, T> {
+    type Output = Result<RecvRef<'a, T>, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = Pin::get_mut(self);
+        let receiver = this
+            .receiver
+            .take()
+            .expect("peek future polled after completion");
+        let active = match receiver.resume() {
+            Ok(active) => active,
+            Err(err) => return Poll::Ready(Err(err)),
+        };
+        match receiver.poll_peek(cx) {
+            Poll::Ready(result) => {
+                let receiver: &'a Receiver<T> = receiver;
+                Poll::Ready(result.map(|(index, hold)| receiver.borrow_peeked(index, hold, active)))
+            }
+            Poll::Pending => {
+                this.receiver = Some(receiver);
+                Poll::Pending
+            }
+        }
+    }
+}
+
 impl<T> Receiver<T> {
     pub(crate) fn new(nexus: Arc<NexusQ<T>>) -> Self {
         let buffer = nexus.buffer.clone();
         let cell = buffer.get(0).expect("buffer is empty");
-        cell.move_to();
+        if nexus.mode == DeliveryMode::Broadcast {
+            cell.move_to();
+        }
         nexus.num_receivers.add(1, Ordering::Relaxed);
+        let position = (nexus.mode == DeliveryMode::Broadcast).then(|| nexus.track_position(1));
         Self {
             nexus,
             buffer,
+            group: None,
             cursor: 1,
+            position,
             previous_cell_index: 0,
             current_event: None,
         }
     }
 
+    /// Create a receiver that starts reading from the current write head. It will receive every value
+    /// sent after it was created but none of the values that were sent before. In work queue mode
+    /// the receiver shares the read head instead, so it can receive values that were sent before.
+    pub(crate) fn at_write_head(nexus: Arc<NexusQ<T>>) -> Self {
+        let buffer = nexus.buffer.clone();
+        // Hold the write head so that no value can be written into the cell before it's held
+        let id = nexus.write_head_wait_strategy.take(&nexus.write_head);
+        let previous_cell_index = nexus.index(id.wrapping_sub(1));
+        if nexus.mode == DeliveryMode::Broadcast {
+            unsafe { buffer.get_unchecked(previous_cell_index) }.move_to();
+        }
+        nexus.num_receivers.add(1, Ordering::Relaxed);
+        // Track the position before releasing the write head so a sender can't evict or skip the
+        // receiver before its position is known
+        let position = (nexus.mode == DeliveryMode::Broadcast).then(|| nexus.track_position(id));
+        nexus.release_write_head(id);
+        Self {
+            nexus,
+            buffer,
+            group: None,
+            cursor: id,
+            position,
+            previous_cell_index,
+            current_event: None,
+        }
+    }
+
     /// Returns a new Sender that can be used to send data to the channel this receiver is connected to.
     #[must_use]
     pub fn new_sender(&self) -> crate::Sender<T> {
         crate::Sender::new(self.nexus.clone())
     }
+
+    /// Returns a new [`Closer`](crate::Closer) that can be used to close the channel this receiver is connected to.
+    #[must_use]
+    pub fn closer(&self) -> crate::Closer<T> {
+        crate::Closer::new(self.nexus.clone())
+    }
+
+    /// Returns true if the channel has been closed. There may still be values left to read.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.nexus.is_closed()
+    }
+
+    /// Returns the number of slots in the channel's buffer, which is rounded up to the next power
+    /// of two unless the channel has an exact capacity. See
+    /// [`Sender::capacity`](crate::Sender::capacity).
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the number of values that have been sent but not yet received by this receiver.
+    /// Receivers that share their position with other receivers return the number of values that
+    /// none of them have received yet. In [`DeliveryMode::Lossy`] mode values that have been
+    /// overwritten aren't counted.
+    ///
+    /// The channel may have changed by the time this returns so it should only be used as a hint.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(4).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// sender.send(2).expect("send failed");
+    /// assert_eq!(receiver.pending(), 2);
+    /// receiver.recv().expect("recv failed");
+    /// assert_eq!(receiver.pending(), 1);
+    /// ```
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        if self.is_lossy() {
+            let latest = self.nexus.latest_lossy();
+            return (latest + 1)
+                .saturating_sub(self.cursor)
+                .min(self.buffer.len());
+        }
+        let from = if self.is_shared() {
+            self.read_head().load(Ordering::Acquire)
+        } else {
+            self.cursor
+        };
+        self.nexus.published_until(from).wrapping_sub(from)
+    }
+
+    /// Returns the number of receivers connected to the channel
+    #[must_use]
+    pub fn receiver_count(&self) -> usize {
+        self.nexus.num_receivers.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of senders connected to the channel
+    #[must_use]
+    pub fn sender_count(&self) -> usize {
+        self.nexus.num_senders.load(Ordering::Relaxed)
+    }
+
+    /// Returns the name given to the channel by [`ChannelBuilder::name`](crate::ChannelBuilder::name)
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.nexus.name.as_deref()
+    }
+
+    /// Returns the sequence number of the next value this receiver will receive. Receivers that
+    /// share their position with other receivers return the sequence number of the next value
+    /// that any of them will receive. See [`Receiver::recv_with_seq`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(3).expect("channel creation failed");
+    /// assert_eq!(receiver.position(), 0);
+    /// sender.send(1).expect("send failed");
+    /// receiver.recv().expect("recv failed");
+    /// assert_eq!(receiver.position(), 1);
+    /// ```
+    #[must_use]
+    pub fn position(&self) -> u64 {
+        if self.is_shared() {
+            return sequence_number(self.read_head().load(Ordering::Acquire));
+        }
+        sequence_number(self.cursor)
+    }
+
+    /// Returns true if this receiver was evicted by the channel's [`StallPolicy`] for holding up
+    /// the channel. Every operation on an evicted receiver fails with [`RecvError::Evicted`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{ChannelBuilder, RecvError, StallPolicy};
+    /// let (sender, mut receiver) = ChannelBuilder::new(8)
+    ///     .stall_policy(StallPolicy::MaxLag(2))
+    ///     .build()
+    ///     .expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// sender.send(2).expect("send failed");
+    /// assert!(!receiver.is_evicted());
+    /// sender.send(3).expect("send failed");
+    /// assert!(receiver.is_evicted());
+    /// assert_eq!(receiver.recv(), Err(RecvError::Evicted));
+    /// ```
+    #[must_use]
+    pub fn is_evicted(&self) -> bool {
+        self.position
+            .as_ref()
+            .is_some_and(|position| position.is_evicted())
+    }
+
+    /// Returns a new receiver that is a member of the named consumer group. The group is created if
+    /// it doesn't exist.
+    ///
+    /// Every consumer group receives every value sent to the channel, but within a group each value
+    /// is received by only one of its members. A new group receives the values sent after it was
+    /// created. Once every member of a group has been dropped the group is removed.
+    ///
+    /// # Errors
+    /// - [`NexusError::GroupsRequireBroadcast`] The channel isn't using [`DeliveryMode::Broadcast`]
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, receiver) = make_channel(4).expect("couldn't construct channel");
+    /// let mut auditor = receiver.join_group("auditors").expect("couldn't join group");
+    /// let mut worker_a = receiver.join_group("workers").expect("couldn't join group");
+    /// let mut worker_b = worker_a.clone();
+    /// drop(receiver);
+    /// sender.send(1).expect("couldn't send");
+    /// sender.send(2).expect("couldn't send");
+    /// assert_eq!(auditor.recv(), Ok(1));
+    /// assert_eq!(auditor.recv(), Ok(2));
+    /// assert_eq!(worker_a.recv(), Ok(1));
+    /// assert_eq!(worker_b.recv(), Ok(2));
+    /// assert!(worker_a.try_recv().is_err());
+    /// ```
+    pub fn join_group(&self, name: &str) -> Result<Self, NexusError> {
+        if self.nexus.mode != DeliveryMode::Broadcast {
+            return Err(NexusError::GroupsRequireBroadcast);
+        }
+        let group = self.nexus.join_group(name);
+        self.nexus.num_receivers.add(1, Ordering::Relaxed);
+        Ok(Self {
+            nexus: self.nexus.clone(),
+            buffer: self.buffer.clone(),
+            group: Some(group),
+            cursor: 1,
+            position: None,
+            previous_cell_index: 0,
+            current_event: None,
+        })
+    }
+
+    /// Returns true if this receiver claims values from a shared read head rather than reading every value
+    fn is_shared(&self) -> bool {
+        self.group.is_some() || matches!(self.nexus.mode, DeliveryMode::WorkQueue)
+    }
+
+    /// Returns true if this receiver holds its place in the buffer which stops senders from
+    /// overwriting values it hasn't read yet
+    fn holds_cell(&self) -> bool {
+        self.group.is_none() && self.nexus.mode == DeliveryMode::Broadcast
+    }
+
+    fn is_lossy(&self) -> bool {
+        self.nexus.mode == DeliveryMode::Lossy
+    }
+
+    /// Stop this receiver from being evicted or moved on by senders until the returned guard is
+    /// dropped. Must be called before reading from the channel or moving the cursor. Receivers
+    /// that can't be evicted and calls made while the receiver is already active get an empty guard.
+    fn activate(&self) -> Result<Active, RecvError> {
+        let Some(position) = self
+            .position
+            .as_ref()
+            .filter(|_| self.nexus.moves_receivers())
+        else {
+            return Ok(Active(None));
+        };
+        if position.is_active() {
+            return Ok(Active(None));
+        }
+        if !position.activate() {
+            return Err(RecvError::Evicted);
+        }
+        Ok(Active(Some(position.clone())))
+    }
+
+    /// Activate the receiver and catch up with a sender that moved it on. Returns
+    /// [`RecvError::Lagged`] with the number of values it missed if it was moved on.
+    fn resume(&mut self) -> Result<Active, RecvError> {
+        let active = self.activate()?;
+        match self.catch_up() {
+            0 => Ok(active),
+            missed => Err(RecvError::Lagged(missed)),
+        }
+    }
+
+    /// Move the cursor to where a sender left this receiver when it dropped values the receiver
+    /// hadn't read, see [`OverflowPolicy::DropOldest`](crate::OverflowPolicy::DropOldest). The
+    /// sender has already moved the receiver's hold. Must be called while the receiver is active.
+    /// Returns the number of values that were missed.
+    fn catch_up(&mut self) -> usize {
+        let Some(position) = &self.position else {
+            return 0;
+        };
+        let cursor = position.cursor.load(Ordering::Acquire);
+        let missed = cursor.wrapping_sub(self.cursor);
+        if missed > 0 {
+            self.cursor = cursor;
+            self.previous_cell_index = self.nexus.index(cursor.wrapping_sub(1));
+            // a pending listener may be waiting on the cell at the old cursor
+            self.current_event = None;
+            self.nexus.record_lagged(missed);
+        }
+        missed
+    }
+
+    /// The read head that this receiver shares with the other members of its group or work queue
+    fn read_head(&self) -> &AtomicUsize {
+        self.group
+            .as_ref()
+            .map_or(&self.nexus.read_head, |group| &group.read_head)
+    }
+
+    /// Attempt to claim the value published with `id` from the shared read head. The cell must have
+    /// published `id` or a later id.
+    ///
+    /// Returns false if another receiver got to the value first.
+    fn claim(&self, id: usize, cell: &Cell<T>) -> Result<bool, RecvError> {
+        if self.nexus.is_tombstone(id) {
+            return Err(RecvError::Disconnected);
+        }
+        let claimed = cell.get_published() == id
+            && self
+                .read_head()
+                .compare_exchange(id, id.wrapping_add(1), Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok();
+        if claimed {
+            self.nexus.record_received(id);
+            self.nexus
+                .update_watermark_after_recv(self.read_head().load(Ordering::Acquire));
+        }
+        Ok(claimed)
+    }
+
+    /// Wait for `cell` to publish the value at the cursor. Lossy senders can overwrite the cell before
+    /// the receiver gets to it, so later values end the wait as well.
+    fn wait_for_cursor(&self, cell: &Cell<T>) {
+        if self.is_lossy() {
+            cell.wait_for_published_or_later(self.cursor);
+        } else {
+            cell.wait_for_published(self.cursor);
+        }
+    }
+
+    /// Wait for the next value on the shared read head and claim it. Returns the claimed cell.
+    fn claim_next(&self) -> Result<&Cell<T>, RecvError> {
+        loop {
+            let id = self.read_head().load(Ordering::Acquire);
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(id)) };
+            cell.wait_for_published_or_later(id);
+            if self.claim(id, cell)? {
+                return Ok(cell);
+            }
+        }
+    }
+
+    /// Hold the cell at the cursor of a lossy receiver and move the cursor on. The value at the cursor
+    /// may already have been overwritten in which case the cursor skips ahead to the oldest value that
+    /// could still be in the buffer. Returns the index of the held cell which must be released with
+    /// [`Cell::move_from`].
+    fn hold_next_lossy(&mut self) -> Result<usize, RecvError> {
+        let index = self.pin_next_lossy()?;
+        self.nexus.record_received(self.cursor);
+        self.cursor = self.cursor.wrapping_add(1);
+        Ok(index)
+    }
+
+    /// Hold the cell at the cursor of a lossy receiver without moving the cursor on. A lagging
+    /// receiver skips ahead as it does in [`Receiver::hold_next_lossy`].
+    fn pin_next_lossy(&mut self) -> Result<usize, RecvError> {
+        let len = self.buffer.len();
+        let mut missed = 0;
+        loop {
+            let index = self.nexus.index(self.cursor);
+            let cell = unsafe { self.buffer.get_unchecked(index) };
+            let published = cell.get_published();
+            if published > self.cursor {
+                // skip ahead to the oldest value that could still be in the buffer
+                let oldest = published.wrapping_sub(len - 1);
+                missed += oldest.wrapping_sub(self.cursor);
+                self.cursor = oldest;
+                continue;
+            }
+            if missed > 0 {
+                self.nexus.record_lagged(missed);
+                return Err(RecvError::Lagged(missed));
+            }
+            if published < self.cursor {
+                return Err(RecvError::NoNewData);
+            }
+            if self.nexus.is_tombstone(self.cursor) {
+                return Err(RecvError::Disconnected);
+            }
+            if cell.try_hold_published(self.cursor) {
+                return Ok(index);
+            }
+            // a sender is writing to the cell
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Move a broadcast receiver's hold from the previous cell to the cell at the cursor and move
+    /// the cursor on to the next id
+    fn advance(&mut self, current_index: usize) {
+        let current_cell = unsafe { self.buffer.get_unchecked(current_index) };
+        let previous_cell = unsafe { self.buffer.get_unchecked(self.previous_cell_index) };
+        current_cell.move_to();
+        previous_cell.move_from();
+
+        self.previous_cell_index = current_index;
+        self.nexus.record_received(self.cursor);
+        self.cursor = self.cursor.wrapping_add(1);
+        self.publish_position();
+        self.nexus.update_watermark_after_recv(self.cursor);
+    }
+
+    /// Let the other receivers know where this receiver is up to
+    fn publish_position(&self) {
+        if let Some(position) = &self.position {
+            position.cursor.store(self.cursor, Ordering::Release);
+        }
+    }
+
+    /// Wait for the next value and borrow it without cloning it. This method will block until a new
+    /// value is available. `T` doesn't need to implement [`Clone`].
+    ///
+    /// Broadcast receivers can't read past the borrowed value until it has been dropped. In
+    /// [`DeliveryMode::WorkQueue`] mode or in a consumer group the value is claimed by this receiver
+    /// and released when the borrow is dropped.
+    ///
+    /// # Errors
+    /// See [`RecvError`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(3).expect("channel creation failed");
+    /// sender.send(vec![1, 2, 3]).expect("send failed");
+    /// let value = receiver.recv_ref().expect("recv failed");
+    /// assert_eq!(value.len(), 3);
+    /// ```
+    pub fn recv_ref(&mut self) -> Result<RecvRef<'_, T>, RecvError> {
+        if self.is_shared() {
+            let cell = self.claim_next()?;
+            return Ok(RecvRef {
+                nexus: &self.nexus,
+                cell,
+                hold: Hold::Claim,
+                position: None,
+                active: Active(None),
+            });
+        }
+        let active = self.resume()?;
+        let current_index = self.nexus.index(self.cursor);
+        let current_cell = unsafe { self.buffer.get_unchecked(current_index) };
+        self.wait_for_cursor(current_cell);
+
+        let (index, hold) = if self.is_lossy() {
+            (self.hold_next_lossy()?, Hold::Pin)
+        } else {
+            if self.nexus.is_tombstone(self.cursor) {
+                return Err(RecvError::Disconnected);
+            }
+            self.advance(current_index);
+            (current_index, Hold::Cursor)
+        };
+        Ok(RecvRef {
+            nexus: &self.nexus,
+            cell: unsafe { self.buffer.get_unchecked(index) },
+            hold,
+            position: self.position.as_deref(),
+            active,
+        })
+    }
+
+    /// Wait for the next value and call `f` with a borrow of it, returning the result. This method
+    /// will block until a new value is available. `T` doesn't need to implement [`Clone`].
+    /// See [`Receiver::recv_ref`].
+    ///
+    /// # Errors
+    /// See [`RecvError`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(3).expect("channel creation failed");
+    /// sender.send(String::from("hello")).expect("send failed");
+    /// assert_eq!(receiver.recv_with(String::len), Ok(5));
+    /// ```
+    pub fn recv_with<R>(&mut self, f: impl FnOnce(&T) -> R) -> Result<R, RecvError> {
+        self.recv_ref().map(|value| f(&value))
+    }
+
+    /// Wait for the next value and take it if this receiver is the last one that will read it,
+    /// otherwise borrow it. This method will block until a new value is available. `T` doesn't need
+    /// to implement [`Clone`] so this can be used to pass on values that can't be copied.
+    ///
+    /// A receiver is the last to read a value once every other receiver has finished reading it. A
+    /// receiver has finished reading a value once it has read the value after it. Values received
+    /// in [`DeliveryMode::WorkQueue`] mode are always taken while values received in
+    /// [`DeliveryMode::Lossy`] mode are always borrowed.
+    ///
+    /// # Errors
+    /// See [`RecvError`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, Received};
+    /// let (sender, mut receiver_a) = make_channel(4).expect("channel creation failed");
+    /// let mut receiver_b = receiver_a.clone();
+    /// sender.send(Box::new(1)).expect("send failed");
+    /// sender.send(Box::new(2)).expect("send failed");
+    /// assert!(matches!(receiver_a.recv_take(), Ok(Received::Borrowed(_))));
+    /// assert!(matches!(receiver_a.recv_take(), Ok(Received::Borrowed(_))));
+    /// // receiver a has finished reading the first value so receiver b takes it
+    /// assert!(matches!(receiver_b.recv_take(), Ok(Received::Owned(value)) if *value == 1));
+    /// ```
+    pub fn recv_take(&mut self) -> Result<Received<'_, T>, RecvError> {
+        self.recv_ref().map(RecvRef::into_received)
+    }
+
+    /// Wait for the next value and borrow it without receiving it. The receiver's position is left
+    /// untouched so the next receive returns the same value. This method will block until a new
+    /// value is available. `T` doesn't need to implement [`Clone`].
+    ///
+    /// In [`DeliveryMode::Lossy`] mode a receiver that has fallen behind skips ahead to the oldest
+    /// value still in the channel as it would when receiving.
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Unsupported`] for receivers that share their position. See
+    /// [`RecvError`] for the others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(3).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// assert_eq!(*receiver.peek().expect("peek failed"), 1);
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// ```
+    pub fn peek(&mut self) -> Result<RecvRef<'_, T>, RecvError> {
+        if self.is_shared() {
+            return Err(RecvError::Unsupported);
+        }
+        let active = self.resume()?;
+        let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(self.cursor)) };
+        self.wait_for_cursor(cell);
+        let (index, hold) = self.pin_next()?;
+        Ok(self.borrow_peeked(index, hold, active))
+    }
+
+    /// Attempt to immediately borrow the next value without receiving it. See [`Receiver::peek`].
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Unsupported`] for receivers that share their position and
+    /// [`RecvError::NoNewData`] if there is no value to read. See [`RecvError`] for the others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, RecvError};
+    /// let (sender, mut receiver) = make_channel(3).expect("channel creation failed");
+    /// assert!(matches!(receiver.try_peek(), Err(RecvError::NoNewData)));
+    /// sender.send(1).expect("send failed");
+    /// assert_eq!(*receiver.try_peek().expect("peek failed"), 1);
+    /// assert_eq!(receiver.try_recv(), Ok(1));
+    /// ```
+    pub fn try_peek(&mut self) -> Result<RecvRef<'_, T>, RecvError> {
+        if self.is_shared() {
+            return Err(RecvError::Unsupported);
+        }
+        let active = self.resume()?;
+        let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(self.cursor)) };
+        if cell.get_published() < self.cursor {
+            return Err(RecvError::NoNewData);
+        }
+        let (index, hold) = self.pin_next()?;
+        Ok(self.borrow_peeked(index, hold, active))
+    }
+
+    /// Asynchronously wait for the next value and borrow it without receiving it. Lagging in
+    /// [`DeliveryMode::Lossy`] mode is skipped over. See [`Receiver::peek`].
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Unsupported`] for receivers that share their position. See
+    /// [`RecvError`] for the others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel(3).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// assert_eq!(*receiver.peek_async().await.expect("peek failed"), 1);
+    /// assert_eq!(receiver.recv_async().await, Ok(1));
+    ///# });
+    /// ```
+    pub const fn peek_async(&mut self) -> PeekFuture<'_, T> {
+        PeekFuture {
+            receiver: Some(self),
+        }
+    }
+
+    /// Skip over up to `n` values that have already been sent without receiving them. Returns the
+    /// number of values that were skipped. Values that haven't been sent yet are never skipped.
+    ///
+    /// In [`DeliveryMode::Lossy`] mode values that have already been overwritten count as skipped.
+    ///
+    /// `StreamExt::skip` takes precedence when `futures_util::StreamExt` is in scope. Use
+    /// `Receiver::skip(&mut receiver, n)` to call this method instead.
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Unsupported`] for receivers that share their position. See
+    /// [`RecvError`] for the others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(5).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// sender.send(2).expect("send failed");
+    /// sender.send(3).expect("send failed");
+    /// assert_eq!(receiver.skip(2), Ok(2));
+    /// assert_eq!(receiver.recv(), Ok(3));
+    /// assert_eq!(receiver.skip(2), Ok(0));
+    /// ```
+    pub fn skip(&mut self, n: usize) -> Result<usize, RecvError> {
+        if self.is_shared() {
+            return Err(RecvError::Unsupported);
+        }
+        let _active = self.activate()?;
+        self.catch_up();
+        let end = self.cursor.saturating_add(n);
+        let target = if self.is_lossy() {
+            // lossy senders don't wait for receivers so every cell holds the latest value written to it
+            let latest = self.buffer.iter().map(Cell::get_published).max();
+            latest.map_or(self.cursor, |latest| {
+                latest.wrapping_add(1).clamp(self.cursor, end)
+            })
+        } else {
+            let mut id = self.cursor;
+            while id < end {
+                let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(id)) };
+                if cell.get_published() != id || self.nexus.is_tombstone(id) {
+                    break;
+                }
+                id = id.wrapping_add(1);
+            }
+            id
+        };
+        let num_skipped = target.wrapping_sub(self.cursor);
+        self.move_cursor(target);
+        self.nexus.update_watermark();
+        Ok(num_skipped)
+    }
+
+    /// Skip over every value that has already been sent so that the next receive waits for a new
+    /// value. Returns the number of values that were skipped.
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Unsupported`] for receivers that share their position. See
+    /// [`RecvError`] for the others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, RecvError};
+    /// let (sender, mut receiver) = make_channel(5).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// sender.send(2).expect("send failed");
+    /// assert_eq!(receiver.seek_to_latest(), Ok(2));
+    /// assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
+    /// sender.send(3).expect("send failed");
+    /// assert_eq!(receiver.recv(), Ok(3));
+    /// ```
+    pub fn seek_to_latest(&mut self) -> Result<usize, RecvError> {
+        self.skip(usize::MAX)
+    }
+
+    /// Move back to the oldest value that is still in the channel so that it can be received again.
+    /// Returns the number of values that the receiver moved back over. Values that another receiver
+    /// has taken using [`Receiver::recv_take`] can't be received again so the receiver stops just
+    /// after them.
+    ///
+    /// In [`DeliveryMode::Lossy`] mode a receiver that has fallen behind moves forward to the oldest
+    /// value that hasn't been overwritten.
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Unsupported`] for receivers that share their position. See
+    /// [`RecvError`] for the others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(5).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// sender.send(2).expect("send failed");
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// assert_eq!(receiver.seek_to_oldest(), Ok(2));
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// ```
+    pub fn seek_to_oldest(&mut self) -> Result<usize, RecvError> {
+        if self.is_shared() {
+            return Err(RecvError::Unsupported);
+        }
+        let _active = self.activate()?;
+        self.catch_up();
+        let len = self.buffer.len();
+        if self.is_lossy() {
+            let latest = self.buffer.iter().map(Cell::get_published).max();
+            let oldest = latest.map_or(1, |latest| latest.saturating_sub(len - 1).max(1));
+            let num_rewound = self.cursor.saturating_sub(oldest);
+            self.move_cursor(oldest);
+            return Ok(num_rewound);
+        }
+        let nexus = Arc::clone(&self.nexus);
+        // Senders can't overwrite any more values while the write head is held. Every value before
+        // it that is still in the buffer has already been published.
+        let next_id = nexus.write_head_wait_strategy.take(&nexus.write_head);
+        let positions = nexus.lock_positions();
+        // The cell before the new cursor must still hold its value
+        let oldest = next_id.saturating_sub(len).wrapping_add(1).max(1);
+        let mut target = self.cursor;
+        while target > oldest
+            && unsafe {
+                self.buffer
+                    .get_unchecked(self.nexus.index(target.wrapping_sub(1)))
+                    .has_value()
+            }
+        {
+            target = target.wrapping_sub(1);
+        }
+        let num_rewound = self.cursor.wrapping_sub(target);
+        self.move_cursor(target);
+        drop(positions);
+        nexus.release_write_head(next_id);
+        nexus.update_watermark();
+        Ok(num_rewound)
+    }
+
+    /// Move the cursor to `target` and hold the cell before it. The caller must make sure that the
+    /// cell before `target` can't be overwritten until it's held, and must hold the positions lock
+    /// to move a tracked receiver back.
+    fn move_cursor(&mut self, target: usize) {
+        if target == self.cursor {
+            return;
+        }
+        if self.holds_cell() {
+            let index = self.nexus.index(target.wrapping_sub(1));
+            unsafe { self.buffer.get_unchecked(index) }.move_to();
+            unsafe { self.buffer.get_unchecked(self.previous_cell_index) }.move_from();
+            self.previous_cell_index = index;
+        }
+        if target < self.cursor && self.position.is_some() {
+            self.nexus
+                .oldest_unread_hint
+                .fetch_min(target, Ordering::AcqRel);
+        }
+        self.cursor = target;
+        self.publish_position();
+        // a pending listener may be waiting on the cell at the old cursor
+        self.current_event = None;
+    }
+
+    /// Hold the published value at the cursor without moving the cursor on
+    fn pin_next(&mut self) -> Result<(usize, Hold), RecvError> {
+        if self.is_lossy() {
+            return Ok((self.pin_next_lossy()?, Hold::Pin));
+        }
+        if self.nexus.is_tombstone(self.cursor) {
+            return Err(RecvError::Disconnected);
+        }
+        // the cell at the cursor can't be overwritten while the cell before it is held
+        Ok((self.nexus.index(self.cursor), Hold::Peek))
+    }
+
+    fn poll_peek(&mut self, cx: &mut Context<'_>) -> Poll<Result<(usize, Hold), RecvError>> {
+        if self.is_shared() {
+            return Poll::Ready(Err(RecvError::Unsupported));
+        }
+        loop {
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(self.cursor)) };
+            if cell
+                .poll_published(cx, self.cursor, &mut self.current_event)
+                .is_pending()
+            {
+                return Poll::Pending;
+            }
+            match self.pin_next() {
+                Err(RecvError::Lagged(_) | RecvError::NoNewData) => {}
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+
+    fn borrow_peeked(&self, index: usize, hold: Hold, active: Active) -> RecvRef<'_, T> {
+        RecvRef {
+            nexus: &self.nexus,
+            cell: unsafe { self.buffer.get_unchecked(index) },
+            hold,
+            position: None,
+            active,
+        }
+    }
 }
 
 impl<T> Clone for Receiver<T> {
     fn clone(&self) -> Self {
-        debug_assert!(self.current_event.is_none());
-        let previous_cell = self
-            .buffer
-            .get(self.previous_cell_index)
-            .expect("previous cell didn't exist");
-        previous_cell.move_to();
+        // The receiver can't be evicted while its hold is copied. The clone of an evicted receiver
+        // is evicted too.
+        let Ok(_active) = self.activate() else {
+            return Self {
+                nexus: self.nexus.clone(),
+                buffer: self.buffer.clone(),
+                group: None,
+                cursor: self.cursor,
+                position: Some(Arc::new(Position::evicted(self.cursor))),
+                previous_cell_index: self.previous_cell_index,
+                current_event: None,
+            };
+        };
+        // A sender may have moved this receiver on since it was last used
+        let cursor = self.position.as_ref().map_or(self.cursor, |position| {
+            position.cursor.load(Ordering::Acquire)
+        });
+        let previous_cell_index = if cursor == self.cursor {
+            self.previous_cell_index
+        } else {
+            self.nexus.index(cursor.wrapping_sub(1))
+        };
+        // A pending poll stays with this receiver. The clone registers its own listener when polled
+        if let Some(group) = &self.group {
+            group.add_member();
+        } else if self.holds_cell() {
+            let previous_cell = self
+                .buffer
+                .get(previous_cell_index)
+                .expect("previous cell didn't exist");
+            previous_cell.move_to();
+        }
         self.nexus.num_receivers.add(1, Ordering::Relaxed);
         Self {
             nexus: self.nexus.clone(),
             buffer: self.buffer.clone(),
-            cursor: self.cursor,
-            previous_cell_index: self.previous_cell_index,
+            group: self.group.clone(),
+            cursor,
+            position: self
+                .position
+                .as_ref()
+                .map(|_| self.nexus.track_position(cursor)),
+            previous_cell_index,
             current_event: None,
         }
     }
@@ -102,7 +1141,35 @@ impl<T> Clone for Receiver<T> {
 
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
+        if let Some(group) = &self.group {
+            self.nexus.num_receivers.sub(1, Ordering::Relaxed);
+            self.nexus.leave_group(group);
+            self.nexus.update_watermark();
+            return;
+        }
+        if self.is_shared() {
+            if self.nexus.num_receivers.fetch_sub(1, Ordering::Relaxed) == 1 {
+                // Nobody is left to take the remaining values. Release them so that senders waiting
+                // on them wake up and observe the disconnected channel
+                self.nexus
+                    .release_published(&self.nexus.read_head, usize::MAX);
+                self.nexus.update_watermark();
+            }
+            return;
+        }
+        // An evicted receiver has already given up its place in the channel
+        let Ok(_active) = self.activate() else {
+            return;
+        };
+        self.catch_up();
         self.nexus.num_receivers.sub(1, Ordering::Relaxed);
+        if let Some(position) = &self.position {
+            self.nexus.untrack_position(position);
+            self.nexus.update_watermark();
+        }
+        if !self.holds_cell() {
+            return;
+        }
         let previous_cell = self
             .buffer
             .get(self.previous_cell_index)
@@ -115,31 +1182,167 @@ impl<T> Receiver<T>
 where
     T: Clone,
 {
+    /// Read a value that has been claimed from the shared read head and release the claim.
+    /// Work queue values are moved out of the cell while group members clone them as other groups
+    /// may still need to read them.
+    ///
+    /// # Safety
+    /// The value in the cell must have been claimed by this receiver
+    unsafe fn consume(&self, cell: &Cell<T>) -> T {
+        let value = if self.group.is_some() {
+            let value = cell.read();
+            cell.release();
+            value
+        } else {
+            cell.take()
+        };
+        // The tombstone may be waiting on this cell to be released
+        self.nexus.publish_tombstone();
+        value
+    }
+
+    /// Shared read head version of [`Receiver::recv`]
+    fn recv_shared(&self) -> Result<T, RecvError> {
+        let cell = self.claim_next()?;
+        Ok(unsafe { self.consume(cell) })
+    }
+
+    /// Shared read head version of [`Receiver::try_recv_until`]
+    fn try_recv_shared_until(&self, deadline: Instant) -> Result<T, RecvError> {
+        loop {
+            let id = self.read_head().load(Ordering::Acquire);
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(id)) };
+            if cell.wait_for_published_until(id, deadline).is_err() {
+                return Err(RecvError::Timeout);
+            }
+            if self.claim(id, cell)? {
+                return Ok(unsafe { self.consume(cell) });
+            }
+        }
+    }
+
+    /// Shared read head version of [`Receiver::try_recv`]
+    fn try_recv_shared(&self) -> Result<T, RecvError> {
+        loop {
+            let id = self.read_head().load(Ordering::Acquire);
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(id)) };
+            if cell.get_published() < id {
+                return Err(RecvError::NoNewData);
+            }
+            if self.claim(id, cell)? {
+                return Ok(unsafe { self.consume(cell) });
+            }
+        }
+    }
+
+    /// Lossy version of [`Receiver::try_recv`]. Senders don't wait for lossy receivers so the value
+    /// at the cursor may already have been overwritten.
+    fn try_recv_lossy(&mut self) -> Result<T, RecvError> {
+        let index = self.hold_next_lossy()?;
+        let cell = unsafe { self.buffer.get_unchecked(index) };
+        let value = unsafe { cell.read() };
+        cell.move_from();
+        Ok(value)
+    }
+
+    /// Lossy version of [`Stream::poll_next`](futures_util::Stream::poll_next). Lagging is skipped
+    /// over silently.
+    fn poll_next_lossy(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        loop {
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(self.cursor)) };
+            if cell
+                .poll_published(cx, self.cursor, &mut self.current_event)
+                .is_pending()
+            {
+                return Poll::Pending;
+            }
+            match self.try_recv_lossy() {
+                Ok(value) => return Poll::Ready(Some(value)),
+                Err(RecvError::Disconnected) => return Poll::Ready(None),
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Shared read head version of [`Stream::poll_next`](futures_util::Stream::poll_next)
+    fn poll_next_shared(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        loop {
+            let id = self.read_head().load(Ordering::Acquire);
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(id)) };
+            if cell
+                .poll_published(cx, id, &mut self.current_event)
+                .is_pending()
+            {
+                return Poll::Pending;
+            }
+            match self.claim(id, cell) {
+                Ok(true) => return Poll::Ready(Some(unsafe { self.consume(cell) })),
+                Ok(false) => {}
+                Err(_) => return Poll::Ready(None),
+            }
+        }
+    }
+
     /// Wait for the next value to become available and then read it. This method will block until
     /// a new value is available.
     ///
+    /// # Errors
+    /// See [`RecvError`].
+    ///
     /// # Examples
     /// ```rust
     ///# use std::time::{Duration, Instant};
-    ///# use nexusq2::make_channel;
+    ///# use nexusq2::{make_channel, RecvError};
     /// let (mut sender, mut receiver) = make_channel::<usize>(3).expect("channel creation failed");
     /// sender.send(1).expect("send failed");
-    /// assert_eq!(receiver.recv(), 1);
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// drop(sender);
+    /// assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
     /// ```
-    pub fn recv(&mut self) -> T {
-        let current_index = self.cursor.fast_mod(self.buffer.len());
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        if self.is_shared() {
+            return self.recv_shared();
+        }
+        if self.is_lossy() {
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(self.cursor)) };
+            cell.wait_for_published_or_later(self.cursor);
+            return self.try_recv_lossy();
+        }
+        let _active = self.resume()?;
+        let current_index = self.nexus.index(self.cursor);
         let current_cell = unsafe { self.buffer.get_unchecked(current_index) };
 
         current_cell.wait_for_published(self.cursor);
 
-        let previous_cell = unsafe { self.buffer.get_unchecked(self.previous_cell_index) };
-        current_cell.move_to();
-        previous_cell.move_from();
+        if self.nexus.is_tombstone(self.cursor) {
+            return Err(RecvError::Disconnected);
+        }
 
-        self.previous_cell_index = current_index;
-        self.cursor = self.cursor.wrapping_add(1);
+        self.advance(current_index);
 
-        unsafe { current_cell.read() }
+        unsafe { Ok(self.buffer.get_unchecked(current_index).read()) }
+    }
+
+    /// Wait for the next value and return it along with its sequence number. This method will block
+    /// until a new value is available. Sequence numbers start at 0 and increase by one for every
+    /// value sent to the channel so they can be used to match up values across receivers or to find
+    /// values that were missed.
+    ///
+    /// # Errors
+    /// See [`RecvError`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(3).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// sender.send(2).expect("send failed");
+    /// assert_eq!(receiver.recv_with_seq(), Ok((0, 1)));
+    /// assert_eq!(receiver.recv_with_seq(), Ok((1, 2)));
+    /// ```
+    pub fn recv_with_seq(&mut self) -> Result<(u64, T), RecvError> {
+        let value = self.recv_ref()?;
+        Ok((value.seq(), T::clone(&value)))
     }
 
     /// Attempt to read up to `max_results` values from the channel. If there are less than `max_results` values available
@@ -176,20 +1379,56 @@ where
         }
         max_results = max_results.clamp(0, self.buffer.len() - 1);
 
+        if self.is_shared() {
+            buffer.reserve(max_results);
+            let mut num_read = 0;
+            while num_read < max_results {
+                let Ok(value) = self.try_recv_shared() else {
+                    break;
+                };
+                buffer.push(value);
+                num_read += 1;
+            }
+            return num_read;
+        }
+        if self.is_lossy() {
+            buffer.reserve(max_results);
+            let mut num_read = 0;
+            while num_read < max_results {
+                match self.try_recv_lossy() {
+                    Ok(value) => {
+                        buffer.push(value);
+                        num_read += 1;
+                    }
+                    // carry on from the oldest value that's still available
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(_) => break,
+                }
+            }
+            return num_read;
+        }
+
+        let Ok(_active) = self.activate() else {
+            return 0;
+        };
+        self.catch_up();
         buffer.reserve(max_results);
         let mut cell = None;
         let mut cell_index = 0;
         let mut num_read = 0;
         for i in 0..max_results {
-            let index = (self.cursor + i).fast_mod(self.buffer.len());
+            let index = self.nexus.index(self.cursor + i);
             let current_cell = unsafe { self.buffer.get_unchecked(index) };
-            if current_cell.get_published() != self.cursor + i {
+            if current_cell.get_published() != self.cursor + i
+                || self.nexus.is_tombstone(self.cursor + i)
+            {
                 // We have read all available values
                 break;
             }
             unsafe {
                 buffer.push(current_cell.read());
             }
+            self.nexus.record_received(self.cursor + i);
             cell = Some(current_cell);
             cell_index = index;
             num_read += 1;
@@ -202,17 +1441,122 @@ where
             let previous_cell = unsafe { self.buffer.get_unchecked(self.previous_cell_index) };
             previous_cell.move_from();
             self.previous_cell_index = cell_index;
+            self.publish_position();
+            self.nexus.update_watermark_after_recv(self.cursor);
         }
 
         num_read
     }
 
+    /// Wait until at least `min_results` values have been read and then read any other available
+    /// values up to `max_results`. This method will block until `min_results` values are available.
+    /// The read values are appended to the end of `buffer`. A `min_results` of 0 never blocks and
+    /// reads what is available as [`Receiver::try_recv_batch`] does.
+    ///
+    /// Returns the number of values read. If the channel is disconnected before `min_results`
+    /// values are read, the values that were read are returned. Values missed in
+    /// [`DeliveryMode::Lossy`] mode are skipped over.
+    ///
+    /// # Errors
+    /// See [`RecvError`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel::<usize>(10).expect("channel creation failed");
+    /// let handle = std::thread::spawn(move || {
+    ///     for i in 0..4 {
+    ///         sender.send(i).expect("send failed");
+    ///     }
+    /// });
+    /// let mut res = Vec::new();
+    /// assert!(receiver.recv_batch(2, 10, &mut res).expect("recv failed") >= 2);
+    ///# handle.join().expect("couldn't join thread");
+    /// ```
+    pub fn recv_batch(
+        &mut self,
+        min_results: usize,
+        max_results: usize,
+        buffer: &mut Vec<T>,
+    ) -> Result<usize, RecvError> {
+        self.recv_batch_before(None, min_results, max_results, buffer)
+    }
+
+    /// Wait until at least `min_results` values have been read or the deadline is hit and then read
+    /// any other available values up to `max_results`. The read values are appended to the end of
+    /// `buffer`. See [`Receiver::recv_batch`].
+    ///
+    /// Returns the number of values read. If the deadline is hit or the channel is disconnected
+    /// before `min_results` values are read, the values that were read are returned.
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Timeout`] if no value arrives in time. See [`RecvError`] for the
+    /// others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use std::time::{Duration, Instant};
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel::<usize>(10).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// let mut res = Vec::new();
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    /// assert_eq!(receiver.recv_batch_until(deadline, 2, 10, &mut res), Ok(1));
+    /// assert_eq!(res, vec![1]);
+    /// ```
+    pub fn recv_batch_until(
+        &mut self,
+        deadline: Instant,
+        min_results: usize,
+        max_results: usize,
+        buffer: &mut Vec<T>,
+    ) -> Result<usize, RecvError> {
+        self.recv_batch_before(Some(deadline), min_results, max_results, buffer)
+    }
+
+    /// Blocking batch receive with an optional deadline
+    fn recv_batch_before(
+        &mut self,
+        deadline: Option<Instant>,
+        min_results: usize,
+        max_results: usize,
+        buffer: &mut Vec<T>,
+    ) -> Result<usize, RecvError> {
+        if max_results == 0 {
+            return Ok(0);
+        }
+        // nothing has to be waited for so just take what is available
+        if min_results == 0 {
+            return Ok(self.try_recv_batch(max_results, buffer));
+        }
+        let min_results = min_results.min(max_results);
+        let mut num_read = self.try_recv_batch(max_results, buffer);
+        while num_read < min_results {
+            let next = match deadline {
+                Some(deadline) => self.try_recv_until(deadline),
+                None => self.recv(),
+            };
+            match next {
+                Ok(value) => {
+                    buffer.push(value);
+                    num_read += 1;
+                }
+                Err(RecvError::Lagged(_)) => {}
+                Err(err) if num_read == 0 => return Err(err),
+                Err(_) => break,
+            }
+            num_read += self.try_recv_batch(max_results - num_read, buffer);
+        }
+        Ok(num_read)
+    }
+
     /// Wait for the next value to become available for up to the deadline time.
     /// If the next value is available before the deadline it's read otherwise an
     /// error is returned.
     ///
     /// # Errors
-    /// - [`RecvError::Timeout`] The deadline was hit before a new value became available
+    /// Fails with [`RecvError::Timeout`] if no value arrives in time. See [`RecvError`] for the
+    /// others.
     ///
     /// # Examples
     /// ```rust
@@ -223,7 +1567,21 @@ where
     /// assert_eq!(receiver.try_recv_until(deadline), Err(RecvError::Timeout));
     /// ```
     pub fn try_recv_until(&mut self, deadline: Instant) -> Result<T, RecvError> {
-        let current_index = self.cursor.fast_mod(self.buffer.len());
+        if self.is_shared() {
+            return self.try_recv_shared_until(deadline);
+        }
+        if self.is_lossy() {
+            let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(self.cursor)) };
+            if cell
+                .wait_for_published_until(self.cursor, deadline)
+                .is_err()
+            {
+                return Err(RecvError::Timeout);
+            }
+            return self.try_recv_lossy();
+        }
+        let _active = self.resume()?;
+        let current_index = self.nexus.index(self.cursor);
         let current_cell = unsafe { self.buffer.get_unchecked(current_index) };
 
         if current_cell
@@ -233,21 +1591,116 @@ where
             return Err(RecvError::Timeout);
         };
 
-        let previous_cell = unsafe { self.buffer.get_unchecked(self.previous_cell_index) };
-        current_cell.move_to();
-        previous_cell.move_from();
+        if self.nexus.is_tombstone(self.cursor) {
+            return Err(RecvError::Disconnected);
+        }
 
-        self.previous_cell_index = current_index;
-        self.cursor = self.cursor.wrapping_add(1);
+        self.advance(current_index);
+
+        unsafe { Ok(self.buffer.get_unchecked(current_index).read()) }
+    }
+
+    /// Wait for the next value to become available for up to the timeout. See
+    /// [`Receiver::try_recv_until`].
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Timeout`] if no value arrives in time. See [`RecvError`] for the
+    /// others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use std::time::Duration;
+    ///# use nexusq2::{make_channel, RecvError};
+    /// let (mut sender, mut receiver) = make_channel::<usize>(3).expect("channel creation failed");
+    /// assert_eq!(receiver.recv_timeout(Duration::from_millis(10)), Err(RecvError::Timeout));
+    /// ```
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvError> {
+        self.try_recv_until(Instant::now() + timeout)
+    }
+
+    /// Asynchronously wait for the next value. Unlike the [`Stream`](futures_util::Stream)
+    /// implementation the result says why no value was received. Values missed in
+    /// [`DeliveryMode::Lossy`] mode are skipped over.
+    ///
+    /// The future is cancel safe. A value is only taken from the channel when the future completes
+    /// so dropping it early doesn't lose anything.
+    ///
+    /// # Errors
+    /// See [`RecvError`].
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, RecvError};
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel::<usize>(3).expect("channel creation failed");
+    /// sender.send(1).expect("send failed");
+    /// assert_eq!(receiver.recv_async().await, Ok(1));
+    /// drop(sender);
+    /// assert_eq!(receiver.recv_async().await, Err(RecvError::Disconnected));
+    ///# });
+    /// ```
+    pub const fn recv_async(&mut self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            deadline: None,
+        }
+    }
+
+    /// Asynchronously wait for the next value to become available until the deadline. See
+    /// [`Receiver::recv_async`].
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Timeout`] if no value arrives in time. See [`RecvError`] for the
+    /// others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use std::time::{Duration, Instant};
+    ///# use nexusq2::{make_channel, RecvError};
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel::<usize>(3).expect("channel creation failed");
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    /// assert_eq!(receiver.try_recv_until_async(deadline).await, Err(RecvError::Timeout));
+    /// sender.send(1).expect("send failed");
+    /// assert_eq!(receiver.try_recv_until_async(deadline).await, Ok(1));
+    ///# });
+    /// ```
+    pub const fn try_recv_until_async(&mut self, deadline: Instant) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            deadline: Some(Deadline::new(deadline)),
+        }
+    }
 
-        unsafe { Ok(current_cell.read()) }
+    /// Asynchronously wait for the next value to become available for up to the timeout. See
+    /// [`Receiver::recv_async`].
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Timeout`] if no value arrives in time. See [`RecvError`] for the
+    /// others.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use std::time::Duration;
+    ///# use nexusq2::{make_channel, RecvError};
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel::<usize>(3).expect("channel creation failed");
+    /// let timeout = Duration::from_millis(10);
+    /// assert_eq!(receiver.recv_timeout_async(timeout).await, Err(RecvError::Timeout));
+    /// sender.send(1).expect("send failed");
+    /// assert_eq!(receiver.recv_timeout_async(timeout).await, Ok(1));
+    ///# });
+    /// ```
+    pub fn recv_timeout_async(&mut self, timeout: Duration) -> RecvFuture<'_, T> {
+        self.try_recv_until_async(Instant::now() + timeout)
     }
 
     /// Attempts to immediately read the next value. If a new value is not available immediately an
     /// error is returned
     ///
     /// # Errors
-    /// - [`RecvError::NoNewData`] There was no unread data in the channel
+    /// Fails with [`RecvError::NoNewData`] if there is no value to read. See [`RecvError`] for the
+    /// others.
     ///
     /// # Examples
     /// ```rust
@@ -260,21 +1713,27 @@ where
     /// assert_eq!(receiver.try_recv(), Err(RecvError::NoNewData));
     /// ```
     pub fn try_recv(&mut self) -> Result<T, RecvError> {
-        let current_index = self.cursor.fast_mod(self.buffer.len());
+        if self.is_shared() {
+            return self.try_recv_shared();
+        }
+        if self.is_lossy() {
+            return self.try_recv_lossy();
+        }
+        let _active = self.resume()?;
+        let current_index = self.nexus.index(self.cursor);
         let current_cell = unsafe { self.buffer.get_unchecked(current_index) };
 
         if current_cell.get_published() != self.cursor {
             return Err(RecvError::NoNewData);
         }
 
-        let previous_cell = unsafe { self.buffer.get_unchecked(self.previous_cell_index) };
-        current_cell.move_to();
-        previous_cell.move_from();
+        if self.nexus.is_tombstone(self.cursor) {
+            return Err(RecvError::Disconnected);
+        }
 
-        self.previous_cell_index = current_index;
-        self.cursor = self.cursor.wrapping_add(1);
+        self.advance(current_index);
 
-        unsafe { Ok(current_cell.read()) }
+        unsafe { Ok(self.buffer.get_unchecked(current_index).read()) }
     }
 }
 
@@ -284,22 +1743,36 @@ where
 {
     type Item = T;
 
+    /// Polls for the next value. A value is only taken from the channel when it's returned so
+    /// dropping a pending `next()` future doesn't lose anything.
+    ///
+    /// The listener registered by a pending poll is kept by the receiver and reused by the next
+    /// poll, even if it's polled from a different task.
+    ///
+    /// The stream ends once the receiver has been evicted by the channel's [`StallPolicy`].
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut_self = Pin::get_mut(self);
-        let current_index = mut_self.cursor.fast_mod(mut_self.buffer.len());
+        if mut_self.is_shared() {
+            return mut_self.poll_next_shared(cx);
+        }
+        if mut_self.is_lossy() {
+            return mut_self.poll_next_lossy(cx);
+        }
+        let Ok(_active) = mut_self.activate() else {
+            return Poll::Ready(None);
+        };
+        mut_self.catch_up();
+        let current_index = mut_self.nexus.index(mut_self.cursor);
         let current_cell = unsafe { mut_self.buffer.get_unchecked(current_index) };
 
         match current_cell.poll_published(cx, mut_self.cursor, &mut mut_self.current_event) {
             Poll::Ready(_) => {
-                let previous_cell =
-                    unsafe { mut_self.buffer.get_unchecked(mut_self.previous_cell_index) };
-                current_cell.move_to();
-                previous_cell.move_from();
-
-                mut_self.previous_cell_index = current_index;
-                mut_self.cursor = mut_self.cursor.wrapping_add(1);
+                if mut_self.nexus.is_tombstone(mut_self.cursor) {
+                    return Poll::Ready(None);
+                }
+                mut_self.advance(current_index);
 
-                Poll::Ready(current_cell.read_opt())
+                Poll::Ready(unsafe { mut_self.buffer.get_unchecked(current_index) }.read_opt())
             }
             Poll::Pending => Poll::Pending,
         }
@@ -1,13 +1,14 @@
-use crate::prelude::FastMod;
-use crate::wait_strategy::{AsyncEventGuard, Takeable};
-use crate::{cell, NexusQ};
+use crate::timer::Deadline;
+use crate::wait_strategy::{AsyncEventGuard, Wait};
+use crate::{cell, sequence_number, Closer, DeliveryMode, NexusQ, Receiver};
 use alloc::sync::Arc;
 use core::fmt::{Debug, Formatter};
 use futures_util::Sink;
-use portable_atomic::Ordering;
+use portable_atomic::{AtomicUsize, Ordering};
+use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error as ThisError;
 
 /// Errors that can be produced by the send methods on a `NexusQ` sender.
@@ -20,12 +21,73 @@ pub enum SendError<T> {
     /// Failed to send the value before the timeout.
     #[error("timeout while waiting for write slot to become available")]
     Timeout(T),
-    /// There are no more receivers and therefore the channel is disconnected.
-    /// Continued use will always return this error.
+    /// There are no receivers or the channel has been closed and therefore the channel is disconnected.
+    /// Once the channel has been closed continued use will always return this error. Evicted
+    /// receivers don't count as receivers, see [`StallPolicy`](crate::StallPolicy).
     #[error("there are no more receivers. The channel is disconnected")]
     Disconnected(Option<T>),
 }
 
+/// What a sender does with a value when there are no receivers to send it to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoReceiverPolicy {
+    /// Return the value in [`SendError::Disconnected`].
+    #[default]
+    Disconnect,
+    /// Silently drop the value and report success. Receivers that subscribe later won't see it.
+    Discard,
+}
+
+/// What a sender does with a value when the channel is full. Set using
+/// [`ChannelBuilder::overflow_policy`](crate::ChannelBuilder::overflow_policy).
+///
+/// [`DeliveryMode::Lossy`] channels are never full so the policy has no effect on them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Wait for a receiver to make room. Sends that can't wait return [`SendError::Full`] or
+    /// [`SendError::Timeout`].
+    #[default]
+    Block,
+    /// Drop the value being sent and report success. Counted by [`Sender::dropped_count`].
+    DropNewest,
+    /// Evict the oldest value in the channel to make room even if it hasn't been received yet.
+    /// Counted by [`Sender::dropped_count`]. A value that is being received can't be evicted so
+    /// sends wait for the receiver to finish with it as they would with [`OverflowPolicy::Block`].
+    ///
+    /// Broadcast receivers that miss a value get [`RecvError::Lagged`](crate::RecvError::Lagged)
+    /// and continue from the oldest value still in the channel, as they would in
+    /// [`DeliveryMode::Lossy`] mode.
+    DropOldest,
+    /// Return the value in [`SendError::Full`] without waiting.
+    Reject,
+}
+
+/// The last watermark a channel's occupancy crossed.
+///
+/// The occupancy is the number of values that haven't been received by every receiver, see
+/// [`Sender::len`]. Set the watermarks using
+/// [`ChannelBuilder::watermarks`](crate::ChannelBuilder::watermarks).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Watermark {
+    /// The occupancy hasn't reached the high watermark, or has fallen back to the low watermark
+    /// since it last did.
+    #[default]
+    Low,
+    /// The occupancy reached the high watermark and hasn't fallen back to the low watermark yet
+    High,
+}
+
+impl Watermark {
+    /// The watermark stored in the channel's state
+    pub(crate) const fn from_state(state: usize) -> Self {
+        if state == Self::High as usize {
+            Self::High
+        } else {
+            Self::Low
+        }
+    }
+}
+
 trait MessageId {
     fn valid(&self) -> bool;
 }
@@ -40,6 +102,12 @@ impl MessageId for usize {
 #[derive(Default)]
 struct AsyncState {
     id: Option<usize>,
+    // The number of consumers of the claimed id. This is read before the write head is released
+    num_consumers: usize,
+    // Set when poll_ready found the channel full and start_send must apply the overflow policy
+    full: bool,
+    // When receivers that hold up the cell are evicted by the stall policy
+    stall: Option<Deadline>,
     event_guard: Option<Pin<Box<dyn AsyncEventGuard>>>,
 }
 
@@ -48,6 +116,9 @@ impl Debug for AsyncState {
         //write all members of AsyncState. For async_state write "Some" or "None" but not the value of Some (as the value is not Debug)
         f.debug_struct("AsyncState")
             .field("current_cell", &self.id)
+            .field("num_consumers", &self.num_consumers)
+            .field("full", &self.full)
+            .field("stall", &self.stall)
             .field(
                 "async_state",
                 if self.event_guard.is_some() {
@@ -59,39 +130,683 @@ impl Debug for AsyncState {
             .finish()
     }
 }
+
+impl AsyncState {
+    /// Claim an id from the write head and wait for its cell to become available for writing.
+    /// The write head is held until [`AsyncState::start_send`] or [`AsyncState::abandon`] so that
+    /// a send that is never completed can give the id back. Callers have the value ready and call
+    /// [`AsyncState::start_send`] as soon as this is ready so the write head isn't held for long.
+    fn poll_ready<T>(
+        &mut self,
+        nexus: &NexusQ<T>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), SendError<T>>> {
+        // The channel was already found to be full and start_send hasn't been called yet
+        if self.full {
+            return Poll::Ready(Ok(()));
+        }
+        // A sender that already claimed an id must finish writing it to release the write head
+        if self.id.is_none() && nexus.num_receivers.load(Ordering::Relaxed) == 0 {
+            return match nexus.no_receiver_policy {
+                NoReceiverPolicy::Disconnect => Poll::Ready(Err(SendError::Disconnected(None))),
+                // start_send will discard the value as no id has been claimed
+                NoReceiverPolicy::Discard => Poll::Ready(Ok(())),
+            };
+        }
+        //claim the id first
+        let id = match self.id {
+            None => {
+                match nexus.write_head_wait_strategy.poll(
+                    cx,
+                    &nexus.write_head,
+                    &mut self.event_guard,
+                ) {
+                    Poll::Ready(id) => {
+                        if nexus.is_closed() {
+                            nexus.close_with(id);
+                            return Poll::Ready(Err(SendError::Disconnected(None)));
+                        }
+                        self.id = Some(id);
+                        id
+                    }
+                    Poll::Pending => {
+                        return Poll::Pending;
+                    }
+                }
+            }
+            Some(id) => id,
+        };
+
+        let cell_index = nexus.index(id);
+
+        let cell = unsafe { nexus.buffer.get_unchecked(cell_index) };
+
+        // lossy senders don't wait for the cell to be read
+        if nexus.mode == DeliveryMode::Lossy {
+            return Poll::Ready(Ok(()));
+        }
+
+        if !cell.safe_to_write() && !nexus.make_room(id) {
+            // start_send will apply the overflow policy to the value
+            nexus.release_write_head(id);
+            self.id = None;
+            self.full = true;
+            return Poll::Ready(Ok(()));
+        }
+
+        //wait for the cell to become available for writing
+        loop {
+            match cell.poll_write_safe(cx, &mut self.event_guard) {
+                Poll::Ready(()) => {
+                    debug_assert!(self.event_guard.is_none());
+                    self.stall = None;
+                    self.num_consumers = nexus.num_consumers();
+                    return Poll::Ready(Ok(()));
+                }
+                Poll::Pending => {
+                    debug_assert!(self.event_guard.is_some());
+                    // closing the channel wakes senders waiting on a full cell
+                    if nexus.is_closed() {
+                        self.abandon(nexus);
+                        return Poll::Ready(Err(SendError::Disconnected(None)));
+                    }
+                    if !nexus.poll_stalled(id, &mut self.stall, cx) {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Write the value to the cell claimed by [`AsyncState::poll_ready`]. If no id was claimed the
+    /// channel was full or there are no receivers and the value is handled according to the
+    /// channel's policies.
+    fn start_send<T>(&mut self, nexus: &NexusQ<T>, item: T) -> Result<(), SendError<T>> {
+        debug_assert!(self.event_guard.is_none());
+
+        let Some(id) = self.id.take() else {
+            if core::mem::take(&mut self.full) {
+                return nexus.overflow(item);
+            }
+            // poll_ready found no receivers and the policy is to discard the value
+            debug_assert_eq!(nexus.no_receiver_policy, NoReceiverPolicy::Discard);
+            return Ok(());
+        };
+        nexus.release_write_head(id.wrapping_add(1));
+        let cell_index = nexus.index(id);
+        let cell = unsafe { nexus.buffer.get_unchecked(cell_index) };
+        if nexus.mode == DeliveryMode::Lossy {
+            cell.overwrite_and_publish(item, id);
+        } else {
+            cell.write_and_publish(item, id, self.num_consumers);
+        }
+        nexus.record_sent(id);
+        Ok(())
+    }
+
+    /// Give up on a pending send. The claimed id is given back to the write head so that the next
+    /// sender can use it. Nothing is published for the id so receivers never see a gap.
+    fn abandon<T>(&mut self, nexus: &NexusQ<T>) {
+        self.event_guard = None;
+        self.full = false;
+        self.stall = None;
+        if let Some(id) = self.id.take() {
+            nexus.release_write_head(id);
+        }
+    }
+}
+
+/// A future that sends a value to the channel. Created by [`Sender::send_async`] and its deadline
+/// variants. The future doesn't depend on an async runtime.
+pub struct SendFuture<'a, T> {
+    sender: &'a Sender<T>,
+    value: Option<T>,
+    state: AsyncState,
+    deadline: Option<Deadline>,
+}
+
+impl<T> Debug for SendFuture<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SendFuture")
+            .field("state", &self.state)
+            .field("deadline", &self.deadline)
+            .finish_non_exhaustive()
+    }
+}
+
+// The value is never pinned
+impl<T> Unpin for SendFuture<'_, T> {}
+
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T> Send for SendFuture<'_, T> where T: Send {}
+
+impl<T> Future for SendFuture<'_, T>
+where
+    T: Send,
+{
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = Pin::get_mut(self);
+        let nexus = this.sender.nexus.as_ref();
+        let Some(value) = this.value.take() else {
+            panic!("send future polled after completion");
+        };
+        match this.state.poll_ready(nexus, cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(this.state.start_send(nexus, value)),
+            Poll::Ready(Err(_)) => Poll::Ready(Err(SendError::Disconnected(Some(value)))),
+            Poll::Pending => {
+                if this
+                    .deadline
+                    .as_mut()
+                    .is_some_and(|deadline| deadline.poll_elapsed(cx))
+                {
+                    this.state.abandon(nexus);
+                    return Poll::Ready(Err(SendError::Timeout(value)));
+                }
+                this.value = Some(value);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        self.state.abandon(&self.sender.nexus);
+    }
+}
+
+/// A claimed slot in the channel that a value can be sent to without waiting. Created by
+/// [`Sender::reserve`] and its variants.
+///
+/// Other senders wait while a permit is held so it should be used or dropped promptly. Dropping
+/// the permit gives the slot back to the channel without sending anything.
+pub struct Permit<'a, T> {
+    sender: &'a Sender<T>,
+    // None if the value will be discarded as there are no receivers or the channel is full
+    id: Option<usize>,
+    num_consumers: usize,
+    // Set if the value will be discarded as the channel is full
+    dropped: bool,
+}
+
+impl<T> Debug for Permit<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Permit")
+            .field("id", &self.id)
+            .field("num_consumers", &self.num_consumers)
+            .field("dropped", &self.dropped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Permit<'_, T> {
+    /// Returns the sequence number the value will be given. Returns None if the value will be
+    /// discarded because there are no receivers or the channel is full. See
+    /// [`Sender::send_with_seq`].
+    #[must_use]
+    pub fn seq(&self) -> Option<u64> {
+        self.id.map(sequence_number)
+    }
+
+    /// Send the value to the claimed slot. This never waits.
+    pub fn send(self, value: T) {
+        let nexus = self.sender.nexus.as_ref();
+        if let Some(id) = self.id {
+            nexus.release_write_head(id.wrapping_add(1));
+            let cell = unsafe { nexus.buffer.get_unchecked(nexus.index(id)) };
+            if nexus.mode == DeliveryMode::Lossy {
+                cell.overwrite_and_publish(value, id);
+            } else {
+                cell.write_and_publish(value, id, self.num_consumers);
+            }
+            nexus.record_sent(id);
+        } else if self.dropped {
+            nexus.record_dropped();
+        }
+        // the write head has been released
+        core::mem::forget(self);
+    }
+
+    /// Fill the claimed slot in place and send it. See [`Sender::send_with`].
+    ///
+    /// Returns the sequence number the value was given. Returns None if nothing was sent because
+    /// there are no receivers, the channel is full or `fill` left the slot empty.
+    pub fn send_with(self, fill: impl FnOnce(&mut Slot<'_, T>)) -> Option<u64> {
+        let nexus = self.sender.nexus.as_ref();
+        let Some(id) = self.id else {
+            if self.dropped {
+                nexus.record_dropped();
+            }
+            return None;
+        };
+        let cell = unsafe { nexus.buffer.get_unchecked(nexus.index(id)) };
+        let fill = |value: &mut Option<T>| fill(&mut Slot { value });
+        // the write head is held until the value is published so the id can be given back if the
+        // slot is left empty or fill panics
+        let published = unsafe {
+            if nexus.mode == DeliveryMode::Lossy {
+                cell.fill_and_overwrite(fill, id)
+            } else {
+                cell.fill_and_publish(fill, id, self.num_consumers)
+            }
+        };
+        if !published {
+            return None;
+        }
+        nexus.release_write_head(id.wrapping_add(1));
+        nexus.record_sent(id);
+        core::mem::forget(self);
+        Some(sequence_number(id))
+    }
+}
+
+impl<T> Drop for Permit<'_, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.sender.nexus.release_write_head(id);
+        }
+    }
+}
+
+/// The storage of a slot in the channel that is being filled in place. Given to the closure passed
+/// to [`Sender::send_with`].
+///
+/// The slot holds the value that was previously sent to it unless a receiver moved it out, for
+/// example a work queue receiver or [`Receiver::recv_take`](crate::Receiver::recv_take).
+#[derive(Debug)]
+pub struct Slot<'a, T> {
+    value: &'a mut Option<T>,
+}
+
+impl<T> Slot<'_, T> {
+    /// Returns the value previously sent to the slot so that it can be modified in place
+    pub const fn get_mut(&mut self) -> Option<&mut T> {
+        self.value.as_mut()
+    }
+
+    /// Replace the value in the slot
+    pub fn insert(&mut self, value: T) -> &mut T {
+        self.value.insert(value)
+    }
+
+    /// Returns the value previously sent to the slot or inserts one if the slot is empty
+    pub fn get_or_insert_with(&mut self, f: impl FnOnce() -> T) -> &mut T {
+        self.value.get_or_insert_with(f)
+    }
+}
+
+/// A future that claims a slot in the channel. Created by [`Sender::reserve_async`].
+pub struct ReserveFuture<'a, T> {
+    sender: &'a Sender<T>,
+    state: AsyncState,
+}
+
+impl<T> Debug for ReserveFuture<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ReserveFuture")
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T> Send for ReserveFuture<'_, T> where T: Send {}
+
+impl<'a, T> Future for ReserveFuture<'a, T> {
+    type Output = Result<Permit<'a, T>, SendError<()>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = Pin::get_mut(self);
+        match this.state.poll_ready(&this.sender.nexus, cx) {
+            Poll::Ready(Ok(())) if core::mem::take(&mut this.state.full) => {
+                Poll::Ready(this.sender.overflow_permit())
+            }
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(Permit {
+                sender: this.sender,
+                id: this.state.id.take(),
+                num_consumers: this.state.num_consumers,
+                dropped: false,
+            })),
+            Poll::Ready(Err(_)) => Poll::Ready(Err(SendError::Disconnected(None))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Drop for ReserveFuture<'_, T> {
+    fn drop(&mut self) {
+        self.state.abandon(&self.sender.nexus);
+    }
+}
+
+/// A future that waits for the channel to cross a watermark. Created by
+/// [`Sender::wait_for_watermark_async`].
+pub struct WatermarkFuture<'a, T> {
+    sender: &'a Sender<T>,
+    watermark: Watermark,
+    event_guard: Option<Pin<Box<dyn AsyncEventGuard>>>,
+}
+
+impl<T> Debug for WatermarkFuture<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WatermarkFuture")
+            .field("watermark", &self.watermark)
+            .finish_non_exhaustive()
+    }
+}
+
+#[allow(clippy::non_send_fields_in_send_ty)]
+unsafe impl<T> Send for WatermarkFuture<'_, T> where T: Send {}
+
+impl<T> Future for WatermarkFuture<'_, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = Pin::get_mut(self);
+        let nexus = this.sender.nexus.as_ref();
+        Wait::<AtomicUsize>::poll(
+            nexus.watermark_wait_strategy.as_ref(),
+            cx,
+            &nexus.watermark,
+            &(this.watermark as usize),
+            &mut this.event_guard,
+        )
+    }
+}
+
 /// A send handle for the `NexusQ` channel.
 /// This handle can be cloned and sent to other threads.
-/// Senders cannot close the channel and can be created from receiver handles!
+/// Senders can be created from receiver handles! The channel is closed explicitly with [`Sender::close`]
+/// or once every sender has been dropped. Receivers will get
+/// [`RecvError::Disconnected`](crate::RecvError::Disconnected) after reading the remaining values.
 #[derive(Debug)]
 pub struct Sender<T> {
     nexus: Arc<NexusQ<T>>,
     buffer: Arc<[cell::Cell<T>]>,
     // Only used for async send
     async_state: AsyncState,
+    // A value given to the sink that hasn't been sent yet
+    sink_item: Option<T>,
 }
 
+// The value held by the sink is never pinned
+impl<T> Unpin for Sender<T> {}
+
 #[allow(clippy::non_send_fields_in_send_ty)]
 unsafe impl<T> Send for Sender<T> {}
+// Shared senders claim ids from the write head. The async state is only used through `&mut self`
+unsafe impl<T> Sync for Sender<T> where T: Send {}
 
 impl<T> Sender<T> {
     pub(crate) fn new(nexus: Arc<NexusQ<T>>) -> Self {
         let buffer = nexus.buffer.clone();
+        nexus.num_senders.add(1, Ordering::Relaxed);
         Self {
             nexus,
             buffer,
             async_state: AsyncState::default(),
+            sink_item: None,
         }
     }
+
+    /// Close the channel. Further sends will fail with [`SendError::Disconnected`] and receivers will
+    /// return [`RecvError::Disconnected`](crate::RecvError::Disconnected) once they have read the
+    /// remaining values. See [`Closer::close`] for details.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, RecvError, SendError};
+    /// let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+    /// let sender_b = sender.clone();
+    /// sender.send(1).expect("couldn't send");
+    /// sender.close();
+    /// assert_eq!(sender_b.send(2), Err(SendError::Disconnected(Some(2))));
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+    /// ```
+    pub fn close(&self) {
+        self.nexus.close();
+    }
+
+    /// Returns true if the channel has been closed
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.nexus.is_closed()
+    }
+
+    /// Returns the number of slots in the channel's buffer. This is the requested size rounded up
+    /// to the next power of two, or the requested size itself if the channel was built with
+    /// [`ChannelBuilder::exact_capacity`](crate::ChannelBuilder::exact_capacity). Broadcast
+    /// receivers hold the slot before the next value they'll read so at most `capacity() - 1`
+    /// values can be waiting for them.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.nexus.buffer.len()
+    }
+
+    /// Returns the number of values in the channel that haven't been received by every receiver.
+    /// In [`DeliveryMode::Lossy`] mode this is the number of values held by the channel as
+    /// receivers aren't tracked.
+    ///
+    /// The channel may have changed by the time this returns so it should only be used as a hint.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+    /// assert!(sender.is_empty());
+    /// sender.send(1).expect("couldn't send");
+    /// sender.send(2).expect("couldn't send");
+    /// sender.send(3).expect("couldn't send");
+    /// assert_eq!(sender.len(), 3);
+    /// assert!(sender.is_full());
+    /// receiver.recv().expect("couldn't receive");
+    /// assert_eq!(sender.len(), 2);
+    /// assert!(!sender.is_full());
+    /// ```
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nexus.len()
+    }
+
+    /// Returns true if there are no values in the channel that haven't been received by every
+    /// receiver. See [`Sender::len`].
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if the next value sent would have to wait for a receiver to make room for it.
+    /// Lossy channels are never full. See [`Sender::len`].
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.nexus.is_full()
+    }
+
+    /// Returns the number of receivers connected to the channel
+    #[must_use]
+    pub fn receiver_count(&self) -> usize {
+        self.nexus.num_receivers.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of senders connected to the channel
+    #[must_use]
+    pub fn sender_count(&self) -> usize {
+        self.nexus.num_senders.load(Ordering::Relaxed)
+    }
+
+    /// Returns the name given to the channel by [`ChannelBuilder::name`](crate::ChannelBuilder::name)
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.nexus.name.as_deref()
+    }
+
+    /// Returns the last watermark the channel crossed. Channels without watermarks are always at
+    /// [`Watermark::Low`].
+    ///
+    /// The watermark is updated as values are sent and received so it may be briefly out of date.
+    #[must_use]
+    pub fn watermark(&self) -> Watermark {
+        Watermark::from_state(self.nexus.watermark.load(Ordering::Acquire))
+    }
+
+    /// Block until the channel is at the given watermark. Returns immediately if it already is.
+    /// Producers can wait for [`Watermark::Low`] to slow down before the channel is full.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use nexusq2::{ChannelBuilder, Watermark};
+    /// let (sender, mut receiver) = ChannelBuilder::new(8)
+    ///     .watermarks(1, 4)
+    ///     .build()
+    ///     .expect("couldn't construct channel");
+    /// for value in 0..4 {
+    ///     sender.send(value).expect("couldn't send");
+    /// }
+    /// assert_eq!(sender.watermark(), Watermark::High);
+    /// let handle = std::thread::spawn(move || {
+    ///     for _ in 0..3 {
+    ///         receiver.recv().expect("couldn't receive");
+    ///     }
+    /// });
+    /// sender.wait_for_watermark(Watermark::Low);
+    /// assert!(sender.len() <= 1);
+    /// handle.join().expect("couldn't join receiver");
+    /// ```
+    pub fn wait_for_watermark(&self, watermark: Watermark) {
+        Wait::<AtomicUsize>::wait_for(
+            self.nexus.watermark_wait_strategy.as_ref(),
+            &self.nexus.watermark,
+            &(watermark as usize),
+        );
+    }
+
+    /// Asynchronously wait for the channel to be at the given watermark. See
+    /// [`Sender::wait_for_watermark`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use nexusq2::{ChannelBuilder, Watermark};
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = ChannelBuilder::new(8)
+    ///     .watermarks(1, 4)
+    ///     .build()
+    ///     .expect("couldn't construct channel");
+    /// for value in 0..4 {
+    ///     sender.send(value).expect("couldn't send");
+    /// }
+    /// assert_eq!(sender.watermark(), Watermark::High);
+    /// for _ in 0..3 {
+    ///     receiver.recv_async().await.expect("couldn't receive");
+    /// }
+    /// sender.wait_for_watermark_async(Watermark::Low).await;
+    ///# });
+    /// ```
+    #[must_use]
+    pub const fn wait_for_watermark_async(&self, watermark: Watermark) -> WatermarkFuture<'_, T> {
+        WatermarkFuture {
+            sender: self,
+            watermark,
+            event_guard: None,
+        }
+    }
+
+    /// Returns the number of values the channel's [`OverflowPolicy`] has dropped, counting both
+    /// new values that were discarded and old values that were evicted.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use nexusq2::{ChannelBuilder, OverflowPolicy};
+    /// let (sender, mut receiver) = ChannelBuilder::new(2)
+    ///     .overflow_policy(OverflowPolicy::DropNewest)
+    ///     .build()
+    ///     .expect("couldn't construct channel");
+    /// sender.send(1).expect("couldn't send");
+    /// sender.send(2).expect("couldn't send");
+    /// assert_eq!(sender.dropped_count(), 1);
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// ```
+    #[must_use]
+    pub fn dropped_count(&self) -> u64 {
+        self.nexus.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns a new [`Closer`] that can be used to close the channel this sender is connected to.
+    #[must_use]
+    pub fn closer(&self) -> Closer<T> {
+        Closer::new(self.nexus.clone())
+    }
+
+    /// Returns a new [`Receiver`] that will receive every value sent after it was created. Values that
+    /// were sent before subscribing are not received, except in [`DeliveryMode::WorkQueue`] mode
+    /// where the new receiver joins the shared read head and competes for any values that haven't
+    /// been received yet.
+    ///
+    /// This waits for the write head so it may block while a sender is waiting for a slow receiver.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+    /// sender.send(1).expect("couldn't send");
+    /// let mut late = sender.subscribe();
+    /// sender.send(2).expect("couldn't send");
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// assert_eq!(late.recv(), Ok(2));
+    /// ```
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver::at_write_head(self.nexus.clone())
+    }
+
+    /// Handle a value sent while there are no receivers according to the channel's [`NoReceiverPolicy`].
+    /// The write head must not be held.
+    fn no_receivers(&self, value: T) -> Result<(), SendError<T>> {
+        match self.nexus.no_receiver_policy {
+            NoReceiverPolicy::Disconnect => Err(SendError::Disconnected(Some(value))),
+            NoReceiverPolicy::Discard => Ok(()),
+        }
+    }
+
+    /// Apply the overflow policy to a reservation that couldn't be made because the channel is
+    /// full. The write head must already have been released.
+    fn overflow_permit(&self) -> Result<Permit<'_, T>, SendError<()>> {
+        if self.nexus.overflow_policy == OverflowPolicy::DropNewest {
+            return Ok(Permit {
+                sender: self,
+                id: None,
+                num_consumers: 0,
+                dropped: true,
+            });
+        }
+        Err(SendError::Full(()))
+    }
 }
 
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Self {
         debug_assert!(self.async_state.event_guard.is_none());
         debug_assert!(self.async_state.id.is_none());
+        self.nexus.num_senders.add(1, Ordering::Relaxed);
         Self {
             nexus: self.nexus.clone(),
             buffer: self.buffer.clone(),
             async_state: AsyncState::default(),
+            sink_item: None,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // An async send that was claimed but never completed must give the write head back
+        self.async_state.abandon(&self.nexus);
+        if self.nexus.num_senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // this was the last sender so the channel is now disconnected
+            self.nexus.close();
         }
     }
 }
@@ -103,7 +818,8 @@ where
     /// Send a value to the channel. This function will block until the value is sent.
     ///
     /// # Errors
-    /// - [`SendError::Disconnected`] There are no more receivers. The channel is disconnected
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
     ///
     /// # Examples
     ///
@@ -112,26 +828,69 @@ where
     /// let (sender, mut receiver) = make_channel(5).expect("Failed to make channel");
     /// sender.send(1).expect("Failed to send");
     /// sender.send(2).expect("Failed to send");
-    /// assert_eq!(receiver.recv(), 1);
-    /// assert_eq!(receiver.recv(), 2);
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Ok(2));
     /// ```
     pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.send_with_seq(value).map(|_| ())
+    }
+
+    /// Send a value to the channel and return the sequence number it was given. This function
+    /// will block until the value is sent. Sequence numbers start at 0 and increase by one for
+    /// every value sent to the channel. See [`Receiver::recv_with_seq`].
+    ///
+    /// Returns None if the value was discarded because there are no receivers. See
+    /// [`NoReceiverPolicy::Discard`].
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(5).expect("Failed to make channel");
+    /// assert_eq!(sender.send_with_seq(1), Ok(Some(0)));
+    /// assert_eq!(sender.send_with_seq(2), Ok(Some(1)));
+    /// assert_eq!(receiver.recv_with_seq(), Ok((0, 1)));
+    /// ```
+    pub fn send_with_seq(&self, value: T) -> Result<Option<u64>, SendError<T>> {
         let nexus = self.nexus.as_ref();
         let buffer = self.buffer.as_ref();
 
         let id = nexus.write_head_wait_strategy.take(&nexus.write_head);
-        let cell_index = id.fast_mod(buffer.len());
+        if nexus.is_closed() {
+            nexus.close_with(id);
+            return Err(SendError::Disconnected(Some(value)));
+        }
+        if nexus.mode == DeliveryMode::Lossy {
+            return self.send_lossy(id, value);
+        }
+        let cell_index = nexus.index(id);
         let cell = unsafe { buffer.get_unchecked(cell_index) };
 
-        if cell.wait_for_write_safe() && nexus.num_receivers.load(Ordering::Relaxed) == 0 {
-            return Err(SendError::Disconnected(Some(value)));
+        if !cell.safe_to_write() && !nexus.make_room(id) {
+            nexus.release_write_head(id);
+            return nexus.overflow(value).map(|()| None);
+        }
+        match nexus.wait_for_write_safe(id) {
+            Ok(true) if nexus.num_receivers.load(Ordering::Relaxed) == 0 => {
+                nexus.release_write_head(id);
+                return self.no_receivers(value).map(|()| None);
+            }
+            Ok(_) => {}
+            Err(_) => {
+                nexus.release_write_head(id);
+                return Err(SendError::Disconnected(Some(value)));
+            }
         }
 
-        nexus.write_head.restore(id.wrapping_add(1));
-        nexus.write_head_wait_strategy.notify_one();
+        let num_consumers = nexus.num_consumers();
+        nexus.release_write_head(id.wrapping_add(1));
 
-        cell.write_and_publish(value, id);
-        Ok(())
+        cell.write_and_publish(value, id, num_consumers);
+        nexus.record_sent(id);
+        Ok(Some(sequence_number(id)))
     }
 
     /// Attempt to send a value to the channel immediately with no waiting. The given value is
@@ -140,7 +899,7 @@ where
     /// # Errors
     /// - [`SendError::Full`] The channel is currently full and cannot accept a new value. The value given
     /// to the send function is returned in the error.
-    /// - [`SendError::Disconnected`] There are no more receivers. The channel is disconnected
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
     /// # Examples
     /// ```rust
     ///# use nexusq2::{make_channel, SendError};
@@ -149,32 +908,65 @@ where
     /// sender.try_send(2).expect("this should be fine");
     /// sender.try_send(3).expect("this should be fine");
     /// assert_eq!(sender.try_send(4), Err(SendError::Full(4)));
-    /// assert_eq!(receiver.recv(), 1);
-    /// assert_eq!(receiver.recv(), 2);
-    /// assert_eq!(receiver.recv(), 3);
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// assert_eq!(receiver.recv(), Ok(3));
     /// ```
     pub fn try_send(&self, value: T) -> Result<(), SendError<T>> {
+        self.try_send_with_seq(value).map(|_| ())
+    }
+
+    /// Attempt to send a value to the channel immediately and return the sequence number it was
+    /// given. See [`Sender::send_with_seq`].
+    ///
+    /// Returns None if the value was discarded because there are no receivers. See
+    /// [`NoReceiverPolicy::Discard`].
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is currently full and cannot accept a new value. The value given
+    ///   to the send function is returned in the error.
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, SendError};
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// assert_eq!(sender.try_send_with_seq(1), Ok(Some(0)));
+    /// assert_eq!(sender.try_send_with_seq(2), Err(SendError::Full(2)));
+    /// ```
+    pub fn try_send_with_seq(&self, value: T) -> Result<Option<u64>, SendError<T>> {
         if self.nexus.num_receivers.load(Ordering::Relaxed) == 0 {
-            return Err(SendError::Disconnected(Some(value)));
+            return self.no_receivers(value).map(|()| None);
         }
-        let Some(id) = self.nexus.write_head_wait_strategy.try_take(&self.nexus.write_head) else {
+        let Some(id) = self
+            .nexus
+            .write_head_wait_strategy
+            .try_take(&self.nexus.write_head)
+        else {
             return Err(SendError::Full(value));
         };
-        let cell_index = id.fast_mod(self.buffer.len());
+        if self.nexus.is_closed() {
+            self.nexus.close_with(id);
+            return Err(SendError::Disconnected(Some(value)));
+        }
+        if self.nexus.mode == DeliveryMode::Lossy {
+            return self.send_lossy(id, value);
+        }
+        let cell_index = self.nexus.index(id);
         let cell = unsafe { self.buffer.get_unchecked(cell_index) };
 
-        if !cell.safe_to_write() {
-            self.nexus.write_head.restore(id);
-            return Err(SendError::Full(value));
+        if !self.nexus.try_make_room(id) {
+            self.nexus.release_write_head(id);
+            return self.nexus.overflow(value).map(|()| None);
         }
 
-        self.nexus.write_head.restore(id.wrapping_add(1));
-
-        self.nexus.write_head_wait_strategy.notify_one();
+        let num_consumers = self.nexus.num_consumers();
+        self.nexus.release_write_head(id.wrapping_add(1));
 
-        cell.write_and_publish(value, id);
+        cell.write_and_publish(value, id, num_consumers);
+        self.nexus.record_sent(id);
 
-        Ok(())
+        Ok(Some(sequence_number(id)))
     }
 
     /// Attempts to send the value before the deadline.
@@ -186,7 +978,8 @@ where
     ///
     /// # Errors
     /// - [`SendError::Timeout`] The value couldn't be sent before the deadline.
-    /// - [`SendError::Disconnected`] There are no more receivers. The channel is disconnected
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
     /// # Examples
     /// ```rust
     ///# use std::time::{Duration, Instant};
@@ -196,113 +989,527 @@ where
     /// sender.try_send_before(2, Instant::now() + Duration::from_secs(1)).expect("this should be fine");
     /// sender.try_send_before(3, Instant::now() + Duration::from_secs(1)).expect("this should be fine");
     /// assert_eq!(sender.try_send_before(4, Instant::now() + Duration::from_millis(10)), Err(SendError::Timeout(4)));
-    /// assert_eq!(receiver.recv(), 1);
-    /// assert_eq!(receiver.recv(), 2);
-    /// assert_eq!(receiver.recv(), 3);
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// assert_eq!(receiver.recv(), Ok(3));
     /// ```
     pub fn try_send_before(&self, value: T, deadline: Instant) -> Result<(), SendError<T>> {
         if deadline < Instant::now() {
             return Err(SendError::Timeout(value));
         }
 
-        let Ok(id) = self.nexus.write_head_wait_strategy
-            .take_before(&self.nexus.write_head, deadline) else { return Err(SendError::Timeout(value)) };
+        let Ok(id) = self
+            .nexus
+            .write_head_wait_strategy
+            .take_before(&self.nexus.write_head, deadline)
+        else {
+            return Err(SendError::Timeout(value));
+        };
+        if self.nexus.is_closed() {
+            self.nexus.close_with(id);
+            return Err(SendError::Disconnected(Some(value)));
+        }
+        if self.nexus.mode == DeliveryMode::Lossy {
+            return self.send_lossy(id, value).map(|_| ());
+        }
 
-        let cell_index = id.fast_mod(self.buffer.len());
+        let cell_index = self.nexus.index(id);
         let cell = unsafe { self.buffer.get_unchecked(cell_index) };
 
-        if let Ok(was_immediate) = cell.wait_for_write_safe_before(deadline) {
-            if was_immediate && self.nexus.num_receivers.load(Ordering::Relaxed) == 0 {
+        if !cell.safe_to_write() && !self.nexus.make_room(id) {
+            self.nexus.release_write_head(id);
+            return self.nexus.overflow(value);
+        }
+        match self.nexus.wait_for_write_safe_before(id, Some(deadline)) {
+            Ok(true) if self.nexus.num_receivers.load(Ordering::Relaxed) == 0 => {
+                self.nexus.release_write_head(id);
+                return self.no_receivers(value);
+            }
+            Ok(_) => {}
+            Err(SendError::Timeout(())) => {
+                self.nexus.release_write_head(id);
+                return Err(SendError::Timeout(value));
+            }
+            Err(_) => {
+                self.nexus.release_write_head(id);
                 return Err(SendError::Disconnected(Some(value)));
             }
-        } else {
-            self.nexus.write_head.restore(id);
-            return Err(SendError::Timeout(value));
         }
 
-        self.nexus.write_head.restore(id.wrapping_add(1));
-
-        self.nexus.write_head_wait_strategy.notify_one();
+        let num_consumers = self.nexus.num_consumers();
+        self.nexus.release_write_head(id.wrapping_add(1));
 
-        cell.write_and_publish(value, id);
+        cell.write_and_publish(value, id, num_consumers);
+        self.nexus.record_sent(id);
         Ok(())
     }
-}
 
-impl<T> Sink<T> for Sender<T>
-where
-    T: Send,
-{
-    type Error = SendError<T>;
+    /// Send every value from the iterator to the channel. The write head is claimed once for a
+    /// contiguous run of ids so values sent in a batch aren't interleaved with values from other
+    /// senders and the write head is handed over less often. Batches longer than the buffer are
+    /// split so that other senders get a turn. This function will block until every value is sent.
+    ///
+    /// Returns the number of values that were sent. Values dropped by
+    /// [`OverflowPolicy::DropNewest`] or discarded by [`NoReceiverPolicy::Discard`] are taken from
+    /// the iterator but aren't counted. Pass the iterator by reference to keep the values that
+    /// weren't sent if an error is returned.
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed. The
+    ///   first value that wasn't sent is returned in the error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(5).expect("Failed to make channel");
+    /// assert_eq!(sender.send_batch(1..=3), Ok(3));
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// assert_eq!(receiver.recv(), Ok(3));
+    /// ```
+    pub fn send_batch(&self, mut values: impl Iterator<Item = T>) -> Result<usize, SendError<T>> {
+        self.send_all(&mut values, &mut 0)
+    }
 
-    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        if self.nexus.num_receivers.load(Ordering::Relaxed) == 0 {
-            return Poll::Ready(Err(SendError::Disconnected(None)));
-        }
-        let mut_self = Pin::get_mut(self);
-        let nexus = mut_self.nexus.as_ref();
-        let buffer = mut_self.buffer.as_ref();
-        unsafe {
-            //claim the id first
-            let id = match mut_self.async_state.id {
-                None => {
-                    match nexus.write_head_wait_strategy.poll(
-                        cx,
-                        &nexus.write_head,
-                        &mut mut_self.async_state.event_guard,
-                    ) {
-                        Poll::Ready(id) => {
-                            mut_self.async_state.id = Some(id);
-                            id
-                        }
-                        Poll::Pending => {
-                            return Poll::Pending;
-                        }
-                    }
+    /// Send every value in the slice to the channel. See [`Sender::send_batch`].
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed. The
+    ///   values that weren't sent are returned in the error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(5).expect("Failed to make channel");
+    /// sender.send_slice(&[1, 2]).expect("Failed to send");
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// assert_eq!(receiver.recv(), Ok(2));
+    /// ```
+    pub fn send_slice<'a>(&self, values: &'a [T]) -> Result<(), SendError<&'a [T]>>
+    where
+        T: Clone,
+    {
+        let mut num_taken = 0;
+        self.send_all(&mut values.iter().cloned(), &mut num_taken)
+            .map(|_| ())
+            .map_err(|err| match err {
+                SendError::Full(_) => SendError::Full(&values[num_taken..]),
+                _ => SendError::Disconnected(Some(&values[num_taken..])),
+            })
+    }
+
+    /// Send values from the iterator in batches that each claim the write head once. Returns the
+    /// number of values that were sent. `num_taken` counts the values taken from the iterator that
+    /// didn't fail, including the ones dropped or discarded by the channel's policies.
+    fn send_all(
+        &self,
+        values: &mut impl Iterator<Item = T>,
+        num_taken: &mut usize,
+    ) -> Result<usize, SendError<T>> {
+        let mut num_sent = 0;
+        let nexus = self.nexus.as_ref();
+        let buffer = self.buffer.as_ref();
+        // Other senders get a turn once a batch has filled the buffer
+        let max_batch = buffer.len();
+
+        // Don't claim the write head until there's a value to send
+        'batches: while let Some(first) = values.next() {
+            let first_id = nexus.write_head_wait_strategy.take(&nexus.write_head);
+            if nexus.is_closed() {
+                nexus.close_with(first_id);
+                return Err(SendError::Disconnected(Some(first)));
+            }
+            let mut id = first_id;
+            let mut next = Some(first);
+            while let Some(value) = next.take() {
+                let cell = unsafe { buffer.get_unchecked(nexus.index(id)) };
+                if nexus.mode != DeliveryMode::Lossy
+                    && !cell.safe_to_write()
+                    && !nexus.make_room(id)
+                {
+                    nexus.release_write_head(id);
+                    nexus.overflow(value)?;
+                    *num_taken += 1;
+                    continue 'batches;
                 }
-                Some(id) => id,
-            };
+                let no_receivers = if nexus.mode == DeliveryMode::Lossy {
+                    nexus.num_receivers.load(Ordering::Relaxed) == 0
+                } else if let Ok(immediate) = nexus.wait_for_write_safe(id) {
+                    immediate && nexus.num_receivers.load(Ordering::Relaxed) == 0
+                } else {
+                    nexus.release_write_head(id);
+                    return Err(SendError::Disconnected(Some(value)));
+                };
+                if no_receivers {
+                    nexus.release_write_head(id);
+                    self.no_receivers(value)?;
+                    *num_taken += 1;
+                    continue 'batches;
+                }
+                // Values are published as they're written so receivers can start reading the
+                // batch while the rest of it waits for space
+                if nexus.mode == DeliveryMode::Lossy {
+                    cell.overwrite_and_publish(value, id);
+                } else {
+                    cell.write_and_publish(value, id, nexus.num_consumers());
+                }
+                nexus.record_sent(id);
+                *num_taken += 1;
+                num_sent += 1;
+                id = id.wrapping_add(1);
+                if id.wrapping_sub(first_id) < max_batch {
+                    next = values.next();
+                }
+            }
+            nexus.release_write_head(id);
+        }
+        Ok(num_sent)
+    }
 
-            debug_assert!(mut_self.async_state.id.is_some());
+    /// Attempts to send the value within the timeout. If the timeout is hit the given value is
+    /// returned in the error. See [`Sender::try_send_before`].
+    ///
+    /// # Errors
+    /// - [`SendError::Timeout`] The value couldn't be sent before the timeout.
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    /// # Examples
+    /// ```rust
+    ///# use std::time::Duration;
+    ///# use nexusq2::{make_channel, SendError};
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// sender.send_timeout(1, Duration::from_millis(10)).expect("this should be fine");
+    /// assert_eq!(sender.send_timeout(2, Duration::from_millis(10)), Err(SendError::Timeout(2)));
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// ```
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendError<T>> {
+        self.try_send_before(value, Instant::now() + timeout)
+    }
 
-            let cell_index = id.fast_mod(buffer.len());
+    /// Asynchronously send a value to the channel. The returned future completes once the value
+    /// has been sent. Unlike the [`Sink`] implementation this doesn't need a mutable sender.
+    ///
+    /// Dropping the future before it completes doesn't send the value.
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Panics
+    /// If the future is polled again after it has completed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// sender.send_async(1).await.expect("couldn't send");
+    /// assert_eq!(receiver.recv_async().await, Ok(1));
+    ///# });
+    /// ```
+    pub const fn send_async(&self, value: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            value: Some(value),
+            state: AsyncState {
+                id: None,
+                num_consumers: 0,
+                full: false,
+                stall: None,
+                event_guard: None,
+            },
+            deadline: None,
+        }
+    }
 
-            let cell = buffer.get_unchecked(cell_index);
+    /// Asynchronously send a value to the channel before the deadline. If the deadline is hit the
+    /// given value is returned in the error. See [`Sender::send_async`].
+    ///
+    /// # Errors
+    /// - [`SendError::Timeout`] The value couldn't be sent before the deadline.
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Panics
+    /// If the future is polled again after it has completed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use std::time::{Duration, Instant};
+    ///# use nexusq2::{make_channel, SendError};
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    /// sender.try_send_before_async(1, deadline).await.expect("this should be fine");
+    /// assert_eq!(
+    ///     sender.try_send_before_async(2, deadline).await,
+    ///     Err(SendError::Timeout(2))
+    /// );
+    ///# });
+    /// ```
+    pub fn try_send_before_async(&self, value: T, deadline: Instant) -> SendFuture<'_, T> {
+        let mut future = self.send_async(value);
+        future.deadline = Some(Deadline::new(deadline));
+        future
+    }
 
-            //wait for the cell to become available for writing
-            match cell.poll_write_safe(cx, &mut mut_self.async_state.event_guard) {
-                Poll::Ready(_) => {
-                    debug_assert!(mut_self.async_state.event_guard.is_none());
-                    nexus.write_head.restore(id.wrapping_add(1));
-                    nexus.write_head_wait_strategy.notify_one();
-                    Poll::Ready(Ok(()))
-                }
-                Poll::Pending => {
-                    debug_assert!(mut_self.async_state.event_guard.is_some());
-                    Poll::Pending
-                }
+    /// Asynchronously send the value within the timeout. If the timeout is hit the given value is
+    /// returned in the error. See [`Sender::send_async`].
+    ///
+    /// # Errors
+    /// - [`SendError::Timeout`] The value couldn't be sent before the timeout.
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Panics
+    /// If the future is polled again after it has completed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use std::time::Duration;
+    ///# use nexusq2::{make_channel, SendError};
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// sender.send_timeout_async(1, Duration::from_millis(10)).await.expect("this should be fine");
+    /// assert_eq!(
+    ///     sender.send_timeout_async(2, Duration::from_millis(10)).await,
+    ///     Err(SendError::Timeout(2))
+    /// );
+    ///# });
+    /// ```
+    pub fn send_timeout_async(&self, value: T, timeout: Duration) -> SendFuture<'_, T> {
+        self.try_send_before_async(value, Instant::now() + timeout)
+    }
+
+    /// Send a value by filling the next slot in the channel in place. `fill` is given the value
+    /// previously sent to the slot so that its allocations can be reused, and large values don't
+    /// have to be moved into the channel.
+    ///
+    /// Other senders wait while `fill` runs. Nothing is sent if the slot is left empty. Waits for
+    /// the slot to become available in the same way as [`Sender::send`].
+    ///
+    /// Returns the sequence number the value was given, or None if nothing was sent.
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// for word in ["hello", "world", "again"] {
+    ///     sender
+    ///         .send_with(|slot| {
+    ///             let buffer = slot.get_or_insert_with(String::new);
+    ///             buffer.clear();
+    ///             buffer.push_str(word);
+    ///         })
+    ///         .expect("couldn't send");
+    ///     assert_eq!(receiver.recv(), Ok(word.to_string()));
+    /// }
+    /// ```
+    pub fn send_with(
+        &self,
+        fill: impl FnOnce(&mut Slot<'_, T>),
+    ) -> Result<Option<u64>, SendError<()>> {
+        Ok(self.reserve()?.send_with(fill))
+    }
+
+    /// Wait for a slot in the channel and claim it. The value can then be sent using the returned
+    /// [`Permit`] without waiting. This lets the value be built once there's room for it.
+    ///
+    /// Other senders wait while the permit is held. Dropping the permit gives the slot back.
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// let permit = sender.reserve().expect("couldn't reserve");
+    /// assert_eq!(permit.seq(), Some(0));
+    /// permit.send(String::from("hello"));
+    /// assert_eq!(receiver.recv(), Ok(String::from("hello")));
+    /// ```
+    pub fn reserve(&self) -> Result<Permit<'_, T>, SendError<()>> {
+        let nexus = self.nexus.as_ref();
+        let id = nexus.write_head_wait_strategy.take(&nexus.write_head);
+        if nexus.is_closed() {
+            nexus.close_with(id);
+            return Err(SendError::Disconnected(None));
+        }
+        if nexus.mode != DeliveryMode::Lossy {
+            let cell = unsafe { nexus.buffer.get_unchecked(nexus.index(id)) };
+            if !cell.safe_to_write() && !nexus.make_room(id) {
+                nexus.release_write_head(id);
+                return self.overflow_permit();
+            }
+            if nexus.wait_for_write_safe(id).is_err() {
+                nexus.release_write_head(id);
+                return Err(SendError::Disconnected(None));
             }
         }
+        self.permit(id)
     }
 
-    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
-        debug_assert!(self.async_state.id.is_some());
-        debug_assert!(self.async_state.event_guard.is_none());
+    /// Attempt to claim a slot in the channel immediately. See [`Sender::reserve`].
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is currently full or another sender holds the write head.
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::{make_channel, SendError};
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// let permit = sender.try_reserve().expect("couldn't reserve");
+    /// assert!(matches!(sender.try_reserve(), Err(SendError::Full(()))));
+    /// permit.send(1);
+    /// assert!(matches!(sender.try_reserve(), Err(SendError::Full(()))));
+    /// assert_eq!(receiver.recv(), Ok(1));
+    /// ```
+    pub fn try_reserve(&self) -> Result<Permit<'_, T>, SendError<()>> {
+        let nexus = self.nexus.as_ref();
+        let Some(id) = nexus.write_head_wait_strategy.try_take(&nexus.write_head) else {
+            return Err(SendError::Full(()));
+        };
+        if nexus.is_closed() {
+            nexus.close_with(id);
+            return Err(SendError::Disconnected(None));
+        }
+        if nexus.mode != DeliveryMode::Lossy && !nexus.try_make_room(id) {
+            nexus.release_write_head(id);
+            return self.overflow_permit();
+        }
+        self.permit(id)
+    }
 
-        let mut_self = unsafe { self.get_unchecked_mut() };
+    /// Asynchronously wait for a slot in the channel and claim it. See [`Sender::reserve`].
+    ///
+    /// Dropping the future before it completes gives the slot back.
+    ///
+    /// # Errors
+    /// - [`SendError::Full`] The channel is full and uses [`OverflowPolicy::Reject`].
+    /// - [`SendError::Disconnected`] There are no more receivers or the channel has been closed.
+    ///
+    /// # Examples
+    /// ```rust
+    ///# use nexusq2::make_channel;
+    ///# tokio::runtime::Runtime::new().expect("couldn't start runtime").block_on(async {
+    /// let (sender, mut receiver) = make_channel(2).expect("couldn't construct channel");
+    /// let permit = sender.reserve_async().await.expect("couldn't reserve");
+    /// permit.send(1);
+    /// assert_eq!(receiver.recv_async().await, Ok(1));
+    ///# });
+    /// ```
+    #[must_use]
+    pub fn reserve_async(&self) -> ReserveFuture<'_, T> {
+        ReserveFuture {
+            sender: self,
+            state: AsyncState::default(),
+        }
+    }
+
+    /// Create a permit for an id that has been claimed from the write head once its cell is safe
+    /// to write to. The write head is released if there are no receivers.
+    fn permit(&self, id: usize) -> Result<Permit<'_, T>, SendError<()>> {
+        let nexus = self.nexus.as_ref();
+        if nexus.num_receivers.load(Ordering::Relaxed) == 0 {
+            nexus.release_write_head(id);
+            return match nexus.no_receiver_policy {
+                NoReceiverPolicy::Disconnect => Err(SendError::Disconnected(None)),
+                NoReceiverPolicy::Discard => Ok(Permit {
+                    sender: self,
+                    id: None,
+                    num_consumers: 0,
+                    dropped: false,
+                }),
+            };
+        }
+        Ok(Permit {
+            sender: self,
+            id: Some(id),
+            num_consumers: nexus.num_consumers(),
+            dropped: false,
+        })
+    }
 
-        let id = unsafe { mut_self.async_state.id.take().unwrap_unchecked() };
-        let cell_index = id.fast_mod(mut_self.buffer.len());
-        let cell = unsafe { mut_self.buffer.get_unchecked(cell_index) };
-        cell.write_and_publish(item, id);
+    /// Send to a lossy channel using an id that has been claimed from the write head. Lossy senders
+    /// never wait for receivers to read the value that's being overwritten.
+    fn send_lossy(&self, id: usize, value: T) -> Result<Option<u64>, SendError<T>> {
+        if self.nexus.num_receivers.load(Ordering::Relaxed) == 0 {
+            self.nexus.release_write_head(id);
+            return self.no_receivers(value).map(|()| None);
+        }
+        self.nexus.release_write_head(id.wrapping_add(1));
+
+        let cell = unsafe { self.buffer.get_unchecked(self.nexus.index(id)) };
+        cell.overwrite_and_publish(value, id);
+        self.nexus.record_sent(id);
+        Ok(Some(sequence_number(id)))
+    }
+}
+
+impl<T> Sender<T> {
+    /// Send the value held by the sink
+    fn poll_send_item(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError<T>>> {
+        let Some(item) = self.sink_item.take() else {
+            return Poll::Ready(Ok(()));
+        };
+        match self.async_state.poll_ready(&self.nexus, cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(self.async_state.start_send(&self.nexus, item)),
+            Poll::Ready(Err(_)) => Poll::Ready(Err(SendError::Disconnected(Some(item)))),
+            Poll::Pending => {
+                self.sink_item = Some(item);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Sink<T> for Sender<T>
+where
+    T: Send,
+{
+    type Error = SendError<T>;
+
+    /// Sends the value given to [`Sink::start_send`], if there is one, so that the sink can take
+    /// the next value. The write head is only claimed while that value is being sent so other
+    /// senders aren't held up by a sink waiting for its next value.
+    ///
+    /// If the send is cancelled the claim is kept and reused by the next send through the sink.
+    /// Dropping the sender gives the claim back to the channel without publishing anything. Use
+    /// [`Sender::send_async`] for sends that give the claim back as soon as they're dropped.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::get_mut(self).poll_send_item(cx)
+    }
+
+    /// Holds the value until the next call to [`Sink::poll_ready`] or [`Sink::poll_flush`] sends it.
+    /// A value that hasn't been flushed is dropped along with the sender.
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let mut_self = Pin::get_mut(self);
+        debug_assert!(mut_self.sink_item.is_none());
+        mut_self.sink_item = Some(item);
         Ok(())
     }
 
-    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::get_mut(self).poll_send_item(cx)
     }
 
-    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+    /// Sends the held value and gives back any claim this sender still has on the write head. Only
+    /// this sender is closed, other senders keep sending. Use [`Sender::close`] to close the channel.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut_self = Pin::get_mut(self);
+        let result = core::task::ready!(mut_self.poll_send_item(cx));
+        mut_self.async_state.abandon(&mut_self.nexus);
+        Poll::Ready(result)
     }
 }
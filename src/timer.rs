@@ -0,0 +1,138 @@
+use portable_atomic::{AtomicU64, Ordering};
+use std::collections::BTreeMap;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::task::{Context, Waker};
+use std::time::Instant;
+
+/// Identifies a registered waker. The id tells apart deadlines that expire at the same instant.
+type Key = (Instant, u64);
+
+/// A runtime agnostic timer used to wake async tasks when their deadline has passed. A single
+/// background thread is started the first time it's used.
+struct Timer {
+    // Ordered by deadline so the next one to expire is first. Entries are removed when their
+    // deadline is dropped so cancelled operations don't pile up.
+    entries: Mutex<BTreeMap<Key, Waker>>,
+    next_id: AtomicU64,
+    condvar: Condvar,
+}
+
+impl Timer {
+    fn get() -> &'static Self {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+        TIMER.get_or_init(|| {
+            std::thread::Builder::new()
+                .name("nexusq-timer".into())
+                .spawn(|| Self::get().run())
+                .expect("couldn't start the timer thread");
+            Self {
+                entries: Mutex::default(),
+                next_id: AtomicU64::new(0),
+                condvar: Condvar::new(),
+            }
+        })
+    }
+
+    fn next_key(&self, deadline: Instant) -> Key {
+        (deadline, self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Wake `waker` once the deadline in `key` has passed. Replaces the waker already registered
+    /// with the key.
+    fn wake_at(&self, key: Key, waker: Waker) {
+        let mut entries = self.entries.lock().expect("timer was poisoned");
+        let is_next = match entries.first_key_value() {
+            Some((next, _)) => key < *next,
+            None => true,
+        };
+        entries.insert(key, waker);
+        drop(entries);
+        if is_next {
+            self.condvar.notify_one();
+        }
+    }
+
+    fn cancel(&self, key: &Key) {
+        self.entries.lock().expect("timer was poisoned").remove(key);
+    }
+
+    fn run(&self) {
+        let mut entries = self.entries.lock().expect("timer was poisoned");
+        loop {
+            let now = Instant::now();
+            let mut expired = Vec::new();
+            while entries
+                .first_key_value()
+                .is_some_and(|((deadline, _), _)| *deadline <= now)
+            {
+                if let Some((_, waker)) = entries.pop_first() {
+                    expired.push(waker);
+                }
+            }
+            if !expired.is_empty() {
+                // Don't hold the lock while waking as the woken task may register a new deadline
+                drop(entries);
+                expired.into_iter().for_each(Waker::wake);
+                entries = self.entries.lock().expect("timer was poisoned");
+                continue;
+            }
+            entries = match entries.first_key_value() {
+                Some(((deadline, _), _)) => {
+                    let timeout = deadline.saturating_duration_since(now);
+                    self.condvar
+                        .wait_timeout(entries, timeout)
+                        .expect("timer was poisoned")
+                        .0
+                }
+                None => self.condvar.wait(entries).expect("timer was poisoned"),
+            };
+        }
+    }
+}
+
+/// A deadline for an async operation. The task is woken once the deadline has passed.
+#[derive(Debug)]
+pub struct Deadline {
+    deadline: Instant,
+    // The key and waker registered with the timer
+    registered: Option<(Key, Waker)>,
+}
+
+impl Deadline {
+    pub const fn new(deadline: Instant) -> Self {
+        Self {
+            deadline,
+            registered: None,
+        }
+    }
+
+    /// Returns true if the deadline has passed. Otherwise the task is woken once it has.
+    pub fn poll_elapsed(&mut self, cx: &Context<'_>) -> bool {
+        if Instant::now() >= self.deadline {
+            return true;
+        }
+        // The task may have moved since the deadline was registered
+        if !self
+            .registered
+            .as_ref()
+            .is_some_and(|(_, waker)| waker.will_wake(cx.waker()))
+        {
+            let timer = Timer::get();
+            let key = self
+                .registered
+                .take()
+                .map_or_else(|| timer.next_key(self.deadline), |(key, _)| key);
+            timer.wake_at(key, cx.waker().clone());
+            self.registered = Some((key, cx.waker().clone()));
+        }
+        false
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        if let Some((key, _)) = &self.registered {
+            Timer::get().cancel(key);
+        }
+    }
+}
@@ -91,7 +91,7 @@ where
             *event_listener = None;
             return Poll::Ready(());
         }
-        let mut listen_guard = event_listener.get_or_insert(self.event.listen());
+        let mut listen_guard = event_listener.get_or_insert_with(|| self.event.listen());
         loop {
             if waitable.check(expected_value) {
                 *event_listener = None;
@@ -162,7 +162,7 @@ where
             *event_listener = None;
             return Poll::Ready(ptr);
         }
-        let mut listen_guard = event_listener.get_or_insert(self.event.listen());
+        let mut listen_guard = event_listener.get_or_insert_with(|| self.event.listen());
 
         loop {
             if let Some(ptr) = takeable.try_take() {
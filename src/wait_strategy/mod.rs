@@ -64,6 +64,40 @@ pub trait Waitable {
     fn check(&self, expected: &Self::Inner) -> bool;
 }
 
+/// A sequence number that only ever increases. Waiting on a sequence completes once it has reached
+/// the expected value, even if it has already moved past it by the time the waiter checks it.
+#[derive(Debug, Default)]
+pub(crate) struct Sequence(AtomicUsize);
+
+impl Sequence {
+    /// Create a new sequence starting at `value`
+    #[must_use]
+    pub const fn new(value: usize) -> Self {
+        Self(AtomicUsize::new(value))
+    }
+
+    /// Load the current value of the sequence
+    pub fn load(&self, order: Ordering) -> usize {
+        self.0.load(order)
+    }
+
+    /// Store a new value in the sequence. The new value must not be less than the current value.
+    pub fn store(&self, value: usize, order: Ordering) {
+        debug_assert!(value >= self.0.load(Ordering::Relaxed));
+        self.0.store(value, order);
+    }
+
+    /// Advance the sequence to `value` if it's greater than the current value. Returns the previous value.
+    pub fn fetch_max(&self, value: usize, order: Ordering) -> usize {
+        self.0.fetch_max(value, order)
+    }
+
+    /// The atomic behind the sequence, for wait strategies that only wait on exact values
+    pub(crate) const fn as_atomic(&self) -> &AtomicUsize {
+        &self.0
+    }
+}
+
 /// Takeable types are container types which hold an inner value which can be taken out of the container.
 /// An example of a takeable in an `Option<T>` where Option is the container and T is the inner value.
 pub trait Takeable {
@@ -272,6 +306,15 @@ impl Waitable for AtomicUsize {
     }
 }
 
+impl Waitable for Sequence {
+    type Inner = usize;
+
+    /// Check to see if the sequence has reached the expected value
+    fn check(&self, expected: &Self::Inner) -> bool {
+        self.0.load(Ordering::Acquire) >= *expected
+    }
+}
+
 impl Takeable for AtomicUsize {
     type Inner = usize;
     const TAKEN: Self::Inner = usize::MAX;
@@ -0,0 +1,141 @@
+use futures_util::{FutureExt, StreamExt};
+use nexusq2::{make_channel, make_channel_with_mode, DeliveryMode, Receiver, Sender};
+use pretty_assertions_sorted::assert_eq;
+use std::time::Duration;
+
+/// Send `0..num` pausing now and then so that receivers are left waiting
+fn send_with_pauses(sender: Sender<usize>, num: usize) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        for value in 0..num {
+            if value % 100 == 0 {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            sender.send(value).expect("couldn't send");
+        }
+    })
+}
+
+/// Receive until the channel is disconnected, cancelling the receive every time it takes too long.
+/// Returns the values that were received and the number of cancelled receives.
+async fn recv_with_cancellation(receiver: &mut Receiver<usize>) -> (Vec<usize>, usize) {
+    let mut received = Vec::new();
+    let mut num_cancelled = 0;
+    loop {
+        match tokio::time::timeout(Duration::from_micros(100), receiver.next()).await {
+            Ok(Some(value)) => received.push(value),
+            Ok(None) => return (received, num_cancelled),
+            Err(_) => num_cancelled += 1,
+        }
+    }
+}
+
+/// Poll the next receive once without waiting on it
+fn poll_once(receiver: &mut Receiver<usize>) -> Option<Option<usize>> {
+    receiver.next().now_or_never()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[cfg_attr(miri, ignore)]
+async fn dropped_next_loses_nothing() {
+    for mode in [DeliveryMode::Broadcast, DeliveryMode::WorkQueue] {
+        let (sender, mut receiver) =
+            make_channel_with_mode(4, mode).expect("couldn't construct channel");
+        let handle = send_with_pauses(sender, 1000);
+        let (received, num_cancelled) = recv_with_cancellation(&mut receiver).await;
+        handle.join().expect("couldn't join thread");
+        assert!(num_cancelled > 0);
+        assert_eq!(received, (0..1000).collect::<Vec<_>>());
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[cfg_attr(miri, ignore)]
+async fn select_between_receivers() {
+    let (sender, mut receiver_a) = make_channel(4).expect("couldn't construct channel");
+    let mut receiver_b = receiver_a.clone();
+    let handle = send_with_pauses(sender, 1000);
+    let mut received_a = Vec::new();
+    let mut received_b = Vec::new();
+    let (mut a_done, mut b_done) = (false, false);
+    // whichever receive finishes first drops the other one while it's pending
+    while !(a_done && b_done) {
+        tokio::select! {
+            value = receiver_a.next(), if !a_done => match value {
+                Some(value) => received_a.push(value),
+                None => a_done = true,
+            },
+            value = receiver_b.next(), if !b_done => match value {
+                Some(value) => received_b.push(value),
+                None => b_done = true,
+            },
+        }
+    }
+    handle.join().expect("couldn't join thread");
+    assert_eq!(received_a, (0..1000).collect::<Vec<_>>());
+    assert_eq!(received_b, (0..1000).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn clone_while_pending() {
+    let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+    assert_eq!(poll_once(&mut receiver), None);
+    let mut clone = receiver.clone();
+    sender.send(1).expect("couldn't send");
+    assert_eq!(receiver.next().await, Some(1));
+    assert_eq!(clone.next().await, Some(1));
+    assert_eq!(poll_once(&mut clone), None);
+    drop(sender);
+    assert_eq!(receiver.next().await, None);
+    assert_eq!(clone.next().await, None);
+}
+
+#[tokio::test]
+async fn clone_work_queue_while_pending() {
+    let (sender, mut receiver) =
+        make_channel_with_mode(4, DeliveryMode::WorkQueue).expect("couldn't construct channel");
+    assert_eq!(poll_once(&mut receiver), None);
+    let mut clone = receiver.clone();
+    assert_eq!(poll_once(&mut clone), None);
+    sender.send(1).expect("couldn't send");
+    sender.send(2).expect("couldn't send");
+    let mut received = vec![
+        receiver.next().await.expect("couldn't receive"),
+        clone.next().await.expect("couldn't receive"),
+    ];
+    received.sort_unstable();
+    assert_eq!(received, vec![1, 2]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[cfg_attr(miri, ignore)]
+async fn listener_follows_the_receiver_to_a_new_task() {
+    let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+    // register a listener that will never be woken
+    assert_eq!(poll_once(&mut receiver), None);
+    let task = tokio::spawn(async move { receiver.next().await });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    sender.send(1).expect("couldn't send");
+    let received = tokio::time::timeout(Duration::from_secs(1), task)
+        .await
+        .expect("the receiving task wasn't woken")
+        .expect("the receiving task panicked");
+    assert_eq!(received, Some(1));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[cfg_attr(miri, ignore)]
+async fn listener_after_blocking_receive() {
+    let (sender, mut receiver) = make_channel(4).expect("couldn't construct channel");
+    assert_eq!(poll_once(&mut receiver), None);
+    sender.send(1).expect("couldn't send");
+    assert_eq!(receiver.try_recv(), Ok(1));
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(10));
+        sender.send(2).expect("couldn't send");
+    });
+    let received = tokio::time::timeout(Duration::from_secs(1), receiver.next())
+        .await
+        .expect("the receiver wasn't woken");
+    assert_eq!(received, Some(2));
+    handle.join().expect("couldn't join thread");
+}
@@ -142,7 +142,7 @@ mod latency_tests {
         let num_sent = 500;
         for _ in 0..num_sent {
             sender.send(Instant::now()).expect("couldn't send");
-            total_duration += receiver.recv().elapsed();
+            total_duration += receiver.recv().expect("couldn't receive").elapsed();
         }
         println!("that took, {}", total_duration.as_nanos() / num_sent);
     }
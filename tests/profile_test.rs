@@ -17,7 +17,7 @@ where
     T: Clone,
 {
     fn test_recv(&mut self) -> T {
-        self.recv()
+        self.recv().expect("couldn't receive")
     }
 
     fn another(&self) -> Self {
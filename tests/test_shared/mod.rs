@@ -123,7 +123,7 @@ fn receive_thread(
 ) -> Vec<usize> {
     let mut values = Vec::with_capacity(num);
     for _ in 0..(num * num_senders) {
-        let v = receiver.recv();
+        let v = receiver.recv().expect("couldn't receive");
         apply_lag(receiver_lag, average_jitter);
         values.push(v);
     }